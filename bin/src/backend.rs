@@ -0,0 +1,205 @@
+//! Key-value storage backends for the run history.
+use std::{cell::RefCell, future::Future, pin::Pin, rc::Rc};
+
+use mogwai::{
+    futures::{
+        channel::oneshot,
+        future::{self, Either},
+    },
+    utils,
+};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{
+    IdbDatabase, IdbObjectStore, IdbOpenDbRequest, IdbRequest, IdbTransactionMode, Storage,
+};
+
+pub type BackendFuture<T> = Pin<Box<dyn Future<Output = Result<T, JsValue>>>>;
+
+/// Stores strings by key.
+pub trait Backend {
+    fn get(&self, key: &str) -> BackendFuture<Option<String>>;
+    fn set(&self, key: &str, value: &str) -> BackendFuture<()>;
+    fn remove(&self, key: &str) -> BackendFuture<()>;
+}
+
+/// The browser's local storage. Small (around 5MB) and synchronous, but
+/// available everywhere.
+pub struct LocalStorage;
+
+impl LocalStorage {
+    fn storage() -> Result<Storage, JsValue> {
+        utils::window()
+            .local_storage()?
+            .ok_or_else(|| JsValue::from_str("local storage is not available"))
+    }
+}
+
+impl Backend for LocalStorage {
+    fn get(&self, key: &str) -> BackendFuture<Option<String>> {
+        let result = LocalStorage::storage().and_then(|storage| storage.get_item(key));
+        Box::pin(async move { result })
+    }
+
+    fn set(&self, key: &str, value: &str) -> BackendFuture<()> {
+        let result = LocalStorage::storage().and_then(|storage| storage.set_item(key, value));
+        Box::pin(async move { result })
+    }
+
+    fn remove(&self, key: &str) -> BackendFuture<()> {
+        let result = LocalStorage::storage().and_then(|storage| storage.remove_item(key));
+        Box::pin(async move { result })
+    }
+}
+
+const DB_NAME: &str = "todo-mvc-bench";
+const DB_VERSION: u32 = 1;
+const STORE_NAME: &str = "kv";
+
+/// Wait for an IndexedDB request to finish, returning its result.
+async fn request_result(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let (tx, rx) = oneshot::channel::<bool>();
+    let tx_success = Rc::new(RefCell::new(Some(tx)));
+    let tx_error = tx_success.clone();
+    let on_success = Closure::once(move |_: JsValue| {
+        if let Some(tx) = tx_success.borrow_mut().take() {
+            let _ = tx.send(true);
+        }
+    });
+    let on_error = Closure::once(move |_: JsValue| {
+        if let Some(tx) = tx_error.borrow_mut().take() {
+            let _ = tx.send(false);
+        }
+    });
+    request.set_onsuccess(Some(on_success.as_ref().unchecked_ref()));
+    request.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+    let succeeded = rx.await.unwrap_or(false);
+    request.set_onsuccess(None);
+    request.set_onerror(None);
+
+    if succeeded {
+        request.result()
+    } else {
+        Err(request
+            .error()?
+            .map(JsValue::from)
+            .unwrap_or_else(|| JsValue::from_str("IndexedDB request failed")))
+    }
+}
+
+/// A single object store in an IndexedDB database. Much larger than local
+/// storage and doesn't block the page.
+pub struct IndexedDb {
+    db: IdbDatabase,
+}
+
+impl IndexedDb {
+    /// Open the database, creating it if need be. Fails if another tab with
+    /// an older version open blocks the upgrade, rather than waiting on it.
+    pub async fn open() -> Result<Self, JsValue> {
+        let factory = utils::window()
+            .indexed_db()?
+            .ok_or_else(|| JsValue::from_str("IndexedDB is not available"))?;
+        let request = factory.open_with_u32(DB_NAME, DB_VERSION)?;
+        let upgrading_request = request.clone();
+        let on_upgrade_needed = Closure::once(move |_: JsValue| {
+            let created = upgrading_request
+                .result()
+                .and_then(|db| db.dyn_into::<IdbDatabase>())
+                .and_then(|db| db.create_object_store(STORE_NAME));
+            if let Err(err) = created {
+                log::error!("could not create the IndexedDB object store: {:?}", err);
+            }
+        });
+        let (tx_blocked, rx_blocked) = oneshot::channel::<()>();
+        let on_blocked = Closure::once(move |_: JsValue| {
+            let _ = tx_blocked.send(());
+        });
+        request.set_onupgradeneeded(Some(on_upgrade_needed.as_ref().unchecked_ref()));
+        request.set_onblocked(Some(on_blocked.as_ref().unchecked_ref()));
+        let opened = match future::select(Box::pin(request_result(&request)), rx_blocked).await {
+            Either::Left((result, _)) => {
+                request.set_onupgradeneeded(None);
+                request.set_onblocked(None);
+                result
+            }
+            Either::Right(_) => {
+                request.set_onblocked(None);
+                IndexedDb::abandon(&request);
+                Err(JsValue::from_str(
+                    "opening IndexedDB is blocked by another tab",
+                ))
+            }
+        };
+        let db = opened?.dyn_into::<IdbDatabase>()?;
+        // Let another tab upgrade the database instead of blocking it in
+        // turn. Requests made after this fail, and are logged by the store.
+        let closing_db = db.clone();
+        let on_version_change = Closure::once_into_js(move |_: JsValue| {
+            log::warn!("closing IndexedDB so another tab can upgrade it");
+            closing_db.close();
+        });
+        db.set_onversionchange(Some(on_version_change.unchecked_ref()));
+        Ok(IndexedDb { db })
+    }
+
+    /// Stop a blocked open request from doing anything once it's unblocked,
+    /// after the history has fallen back to local storage. An upgrade is
+    /// aborted, so the request fails, and a database that opens anyway is
+    /// closed right away.
+    fn abandon(request: &IdbOpenDbRequest) {
+        let upgrading_request = request.clone();
+        let on_upgrade_needed = Closure::once_into_js(move |_: JsValue| {
+            if let Some(transaction) = upgrading_request.transaction() {
+                let _ = transaction.abort();
+            }
+        });
+        let opened_request = request.clone();
+        let on_success = Closure::once_into_js(move |_: JsValue| {
+            if let Ok(db) = opened_request
+                .result()
+                .and_then(|db| db.dyn_into::<IdbDatabase>())
+            {
+                db.close();
+            }
+        });
+        let on_error = Closure::once_into_js(|_: JsValue| {});
+        request.set_onupgradeneeded(Some(on_upgrade_needed.unchecked_ref()));
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    }
+
+    fn request(
+        &self,
+        mode: IdbTransactionMode,
+        f: impl FnOnce(&IdbObjectStore) -> Result<IdbRequest, JsValue>,
+    ) -> BackendFuture<JsValue> {
+        let request = self
+            .db
+            .transaction_with_str_and_mode(STORE_NAME, mode)
+            .and_then(|transaction| transaction.object_store(STORE_NAME))
+            .and_then(|store| f(&store));
+        Box::pin(async move { request_result(&request?).await })
+    }
+}
+
+impl Backend for IndexedDb {
+    fn get(&self, key: &str) -> BackendFuture<Option<String>> {
+        let key = JsValue::from_str(key);
+        let value = self.request(IdbTransactionMode::Readonly, |store| store.get(&key));
+        Box::pin(async move { Ok(value.await?.as_string()) })
+    }
+
+    fn set(&self, key: &str, value: &str) -> BackendFuture<()> {
+        let (key, value) = (JsValue::from_str(key), JsValue::from_str(value));
+        let put = self.request(IdbTransactionMode::Readwrite, |store| {
+            store.put_with_key(&value, &key)
+        });
+        Box::pin(async move { put.await.map(|_| ()) })
+    }
+
+    fn remove(&self, key: &str) -> BackendFuture<()> {
+        let key = JsValue::from_str(key);
+        let delete = self.request(IdbTransactionMode::Readwrite, |store| store.delete(&key));
+        Box::pin(async move { delete.await.map(|_| ()) })
+    }
+}
@@ -5,12 +5,21 @@ use mogwai::{
     time::wait_secs,
 };
 use serde::{Deserialize, Serialize};
-use wasm_bindgen::JsValue;
-use web_sys::{Document, HtmlIFrameElement};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    Document, HtmlIFrameElement, MouseEvent, MouseEventInit, MutationObserverInit, Node,
+    PerformanceResourceTiming, Window,
+};
 
-use todo_mvc_bench_lib::{wait_for, wait_until_next_for, wait_while, Found};
+use todo_mvc_bench_lib::{
+    wait_for, wait_for_mutation, wait_for_paint, wait_until_next_for, wait_while,
+    wait_while_mutation, Found,
+};
 
 use crate::framework_card::CreateTodoMethod;
+pub use todo_mvc_bench_core::benchmark::{
+    exclude_outliers, Benchmark, BenchmarkStep, Exclusion, ResourceSize, ResourceSizes,
+};
 
 use super::framework_card::FrameworkCard;
 
@@ -44,44 +53,182 @@ fn query_selector_all(document: &Dom, selector: &str) -> Vec<Dom> {
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct BenchmarkStep {
-    pub name: String,
-    pub start: f64,
-    pub end: Option<f64>,
-    pub cycles: Option<u64>,
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Benchmark {
-    pub name: String,
-    pub steps: Vec<BenchmarkStep>,
-    pub failed_message: Option<String>,
-    pub language: Option<String>,
-}
-
-impl Benchmark {
-    pub fn new() -> Self {
-        Benchmark {
-            name: "unnamed".into(),
-            steps: vec![],
-            failed_message: None,
-            language: None,
+/// How a step waits on the DOM.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum WaitStrategy {
+    /// Re-check on every tick. Busy, but doesn't miss changes that don't
+    /// mutate the DOM, like visibility from stylesheets.
+    Poll,
+    /// Re-check only when a `MutationObserver` on the document fires.
+    Mutation,
+}
+
+/// The document as a node to observe, and what to observe about it.
+fn observe_document(document: &Dom) -> (Node, MutationObserverInit) {
+    let node = document
+        .clone_as::<Node>()
+        .expect("todo document is not a node");
+    let mut options = MutationObserverInit::new();
+    options
+        .subtree(true)
+        .child_list(true)
+        .attributes(true)
+        .character_data(true);
+    (node, options)
+}
+
+/// Wait for `f` to return `Some`, re-checking it with the given strategy.
+async fn wait_for_dom<T: 'static>(
+    document: &Dom,
+    wait: WaitStrategy,
+    timeout_seconds: f64,
+    f: impl FnMut() -> Option<T> + 'static,
+) -> Result<Found<T>, f64> {
+    match wait {
+        WaitStrategy::Poll => wait_for(timeout_seconds, f).await,
+        WaitStrategy::Mutation => {
+            let (node, options) = observe_document(document);
+            wait_for_mutation(&node, &options, f, timeout_seconds).await
         }
     }
+}
 
-    pub fn total(&self) -> Option<f64> {
-        self.steps.iter().fold(Some(0.0), |may_sum, step| {
-            let sum = may_sum?;
-            let end = step.end?;
-            Some(sum + (end - step.start))
-        })
+/// Wait while `f` returns true, re-checking it with the given strategy.
+async fn wait_while_dom(
+    document: &Dom,
+    wait: WaitStrategy,
+    timeout_seconds: f64,
+    f: impl FnMut() -> bool + 'static,
+) -> Result<Found<()>, f64> {
+    match wait {
+        WaitStrategy::Poll => wait_while(timeout_seconds, f).await,
+        WaitStrategy::Mutation => {
+            let (node, options) = observe_document(document);
+            wait_while_mutation(&node, &options, f, timeout_seconds).await
+        }
+    }
+}
+
+/// Which of the optional step groups a benchmark runs. Loading, creating and
+/// deleting todos always run, the todos have to be created before anything
+/// else and deleted so the next run starts empty.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scenarios {
+    pub edit: bool,
+    pub filter: bool,
+    pub toggle_all: bool,
+    pub complete: bool,
+}
+
+impl Default for Scenarios {
+    fn default() -> Self {
+        Scenarios {
+            edit: true,
+            filter: true,
+            toggle_all: true,
+            complete: true,
+        }
+    }
+}
+
+impl Scenarios {
+    pub const NAMES: [&'static str; 4] = ["edit", "filter", "toggle_all", "complete"];
+
+    /// No optional step groups.
+    pub fn none() -> Self {
+        Scenarios {
+            edit: false,
+            filter: false,
+            toggle_all: false,
+            complete: false,
+        }
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "edit" => Some(&mut self.edit),
+            "filter" => Some(&mut self.filter),
+            "toggle_all" => Some(&mut self.toggle_all),
+            "complete" => Some(&mut self.complete),
+            _ => None,
+        }
+    }
+
+    /// The names of the step groups that run.
+    pub fn enabled(&self) -> Vec<&'static str> {
+        let flags = [self.edit, self.filter, self.toggle_all, self.complete];
+        Scenarios::NAMES
+            .iter()
+            .zip(flags.iter())
+            .filter(|(_, enabled)| **enabled)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+}
+
+/// How much work the steps of a benchmark do.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct BenchConfig {
+    /// Number of todos created.
+    pub todos: usize,
+    /// Number of todos edited by double clicking their label.
+    pub edits: usize,
+    /// Number of todos completed by clicking their toggle.
+    pub toggles: usize,
+    /// Number of todos destroyed by clicking their destroy button before the
+    /// completed todos are cleared.
+    pub destroys: usize,
+    /// How steps wait for the DOM to reach the state they expect.
+    pub wait: WaitStrategy,
+    /// Whether steps end once the todo document has painted, instead of as
+    /// soon as the DOM is ready.
+    pub settle: bool,
+    /// Which optional step groups run.
+    #[serde(default)]
+    pub scenarios: Scenarios,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        BenchConfig::with_todos(100)
+    }
+}
+
+impl BenchConfig {
+    /// The todo counts offered in the navbar.
+    pub const TODO_COUNTS: [usize; 4] = [10, 100, 1000, 10000];
+
+    /// Create, complete all of, and individually edit and destroy a tenth of
+    /// `todos`.
+    pub fn with_todos(todos: usize) -> Self {
+        BenchConfig {
+            todos,
+            edits: todos / 10,
+            toggles: todos,
+            destroys: todos / 10,
+            wait: WaitStrategy::Poll,
+            settle: false,
+            scenarios: Scenarios::default(),
+        }
+    }
+
+    /// How many todos are completed when the delete step clears them: those
+    /// the complete step toggled that weren't destroyed first. None are when
+    /// it doesn't run, the other steps leave every todo active.
+    pub fn completed_at_clear(&self) -> usize {
+        if self.scenarios.complete {
+            self.toggles.min(self.todos).saturating_sub(self.destroys)
+        } else {
+            0
+        }
     }
 }
 
 #[derive(Clone)]
 pub struct Run {
     framework: FrameworkCard,
+    config: BenchConfig,
     reply: broadcast::Sender<Benchmark>,
 }
 
@@ -105,10 +252,32 @@ impl ViewMsg {
     }
 }
 
+/// End the step now that the DOM is ready, or, given the todo window, once
+/// that window has painted.
+async fn end_step(
+    step: &mut BenchmarkStep,
+    paint: Option<&Window>,
+    perf_now: &impl Fn() -> f64,
+) -> Result<(), String> {
+    let dom_ready = perf_now();
+    step.dom_ready = Some(dom_ready);
+    step.end = Some(dom_ready);
+    if let Some(window) = paint {
+        wait_for_paint(window)
+            .await
+            .map_err(|e| format!("could not wait for paint: {:?}", e))?;
+        let painted = perf_now();
+        step.painted = Some(painted);
+        step.end = Some(painted);
+    }
+    Ok(())
+}
+
 async fn load_step(
     iframe: Dom,
     tx: broadcast::Sender<ViewMsg>,
     src: String,
+    paint: Option<&Window>,
     perf_now: impl Fn() -> f64,
 ) -> Result<BenchmarkStep, String> {
     let mut loads = event_stream_with(
@@ -118,57 +287,69 @@ async fn load_step(
             .ok_or_else(|| "iframe is not an event target".to_string())?,
         |ev| Dom::try_from(JsValue::from(ev)).unwrap(),
     );
-    let mut step = BenchmarkStep {
-        name: "initial load".to_string(),
-        start: perf_now(),
-        end: None,
-        cycles: None,
-    };
+    let mut step = BenchmarkStep::new("initial load", perf_now());
 
     tx.broadcast(ViewMsg::IframeSrc(src.clone())).await.unwrap();
     let event = loads.next().await.unwrap();
-    step.end = Some(perf_now());
+    end_step(&mut step, paint, &perf_now).await?;
     Ok(step)
 }
 
+/// Sum up the JS, wasm and CSS downloaded by the iframe's current page.
+fn resource_sizes(iframe: &Dom) -> Result<ResourceSizes, String> {
+    let performance = iframe
+        .clone_as::<HtmlIFrameElement>()
+        .and_then(|iframe| iframe.content_window())
+        .and_then(|window| window.performance())
+        .ok_or_else(|| "iframe has no performance object".to_string())?;
+    let mut sizes = ResourceSizes::default();
+    for entry in performance.get_entries_by_type("resource").iter() {
+        let entry = entry.unchecked_into::<PerformanceResourceTiming>();
+        if let Some(size) = sizes.kind_mut(&entry.name(), &entry.initiator_type()) {
+            size.add(&ResourceSize {
+                transfer: entry.transfer_size(),
+                encoded: entry.encoded_body_size(),
+                decoded: entry.decoded_body_size(),
+            });
+        }
+    }
+    Ok(sizes)
+}
+
 async fn find_todo_input(
     document: Dom,
+    wait: WaitStrategy,
+    paint: Option<&Window>,
     perf_now: impl Fn() -> f64,
 ) -> Result<(Dom, BenchmarkStep), String> {
-    let mut await_todo_step = BenchmarkStep {
-        name: "await todo input".to_string(),
-        start: perf_now(),
-        end: None,
-        cycles: None,
-    };
+    let mut await_todo_step = BenchmarkStep::new("await todo input", perf_now());
     let doc = document.clone();
     let Found {
         found: todo_input, ..
-    } = wait_for(5.0, move || {
+    } = wait_for_dom(&document, wait, 5.0, move || {
         query_selector(&doc, &["#new-todo", ".new-todo"])
     })
     .await
     .map_err(|_| "todo input not found".to_string())?;
-    await_todo_step.end = Some(perf_now());
+    end_step(&mut await_todo_step, paint, &perf_now).await?;
     Ok((todo_input, await_todo_step))
 }
 
-async fn wait_todo_focus(input: Dom, perf_now: impl Fn() -> f64) -> Result<BenchmarkStep, String> {
+async fn wait_todo_focus(
+    input: Dom,
+    paint: Option<&Window>,
+    perf_now: impl Fn() -> f64,
+) -> Result<BenchmarkStep, String> {
     let focus_events = event_stream_with(
         "focus",
         &input.clone_as::<web_sys::EventTarget>().unwrap(),
         |ev| Dom::try_from(JsValue::from(ev)).unwrap(),
     );
-    let mut await_focus_step = BenchmarkStep {
-        name: "await todo focus".to_string(),
-        start: perf_now(),
-        end: None,
-        cycles: None,
-    };
+    let mut await_focus_step = BenchmarkStep::new("await todo focus", perf_now());
     let _ = wait_until_next_for(5.0, focus_events)
         .await
         .map_err(|e| format!("timed out waiting for focus for {} seconds", e))?;
-    await_focus_step.end = Some(perf_now());
+    end_step(&mut await_focus_step, paint, &perf_now).await?;
     Ok(await_focus_step)
 }
 
@@ -176,6 +357,9 @@ async fn create_todos(
     document: Dom,
     input: Dom,
     create_todo_method: CreateTodoMethod,
+    todos: usize,
+    wait: WaitStrategy,
+    paint: Option<&Window>,
     perf_now: impl Fn() -> f64,
 ) -> Result<BenchmarkStep, String> {
     let len = query_selector_all(&document, ".toggle").len();
@@ -183,16 +367,12 @@ async fn create_todos(
         return Err("pre-existing todos".into());
     }
 
-    let mut create_todos_step = BenchmarkStep {
-        name: "create todos".to_string(),
-        start: perf_now(),
-        end: None,
-        cycles: None,
-    };
-    let mut created: u32 = 0;
-    while created < 100 {
+    let mut create_todos_step = BenchmarkStep::new("create todos", perf_now());
+    let mut cycles = vec![];
+    while cycles.len() < todos {
+        let cycle_start = perf_now();
         let len = query_selector_all(&document, ".toggle").len();
-        if len > 100 {
+        if len > todos {
             return Err("created too many todos".into());
         }
 
@@ -209,51 +389,268 @@ async fn create_todos(
             input.clone_as::<web_sys::HtmlInputElement>().unwrap(),
         );
 
-        let document = document.clone();
-        let _ = wait_while(1.0, move || {
-            let new_length = query_selector_all(&document, ".toggle").len();
+        let doc = document.clone();
+        let _ = wait_while_dom(&document, wait, 1.0, move || {
+            let new_length = query_selector_all(&doc, ".toggle").len();
             len + 1 != new_length
         })
         .await
         .map_err(|e| format!("timed out waiting for todo creation for {} seconds", e))?;
-        created += 1;
+        cycles.push(perf_now() - cycle_start);
     }
-    create_todos_step.end = Some(perf_now());
+    create_todos_step.cycles = Some(cycles);
+    end_step(&mut create_todos_step, paint, &perf_now).await?;
     Ok(create_todos_step)
 }
 
+/// Selects the labels of all todos in the list.
+const TODO_LABELS: &str = "#todo-list li label, .todo-list li label";
+
+/// Dispatch a bubbling double click event on the given element.
+fn double_click(el: &Dom) -> Result<(), String> {
+    let mut init = MouseEventInit::new();
+    init.bubbles(true);
+    init.cancelable(true);
+    let event = MouseEvent::new_with_mouse_event_init_dict("dblclick", &init)
+        .map_err(|_| "could not create dblclick event".to_string())?;
+    el.clone_as::<EventTarget>()
+        .ok_or_else(|| "todo label is not an event target".to_string())?
+        .dispatch_event(&event)
+        .map_err(|_| "could not dispatch dblclick event".to_string())?;
+    Ok(())
+}
+
+/// Return the text of the todo label at the given index.
+fn todo_label_text(document: &Dom, index: usize) -> Option<String> {
+    query_selector_all(document, TODO_LABELS)
+        .get(index)?
+        .clone_as::<web_sys::Element>()?
+        .text_content()
+        .map(|text| text.trim().to_string())
+}
+
+async fn edit_todos(
+    document: Dom,
+    create_todo_method: CreateTodoMethod,
+    edits: usize,
+    wait: WaitStrategy,
+    paint: Option<&Window>,
+    perf_now: impl Fn() -> f64,
+) -> Result<BenchmarkStep, String> {
+    let mut edit_todos_step = BenchmarkStep::new("edit todos", perf_now());
+    let mut cycles = vec![];
+    for index in 0..edits {
+        let cycle_start = perf_now();
+        let label = query_selector_all(&document, TODO_LABELS)
+            .get(index)
+            .cloned()
+            .ok_or_else(|| format!("no todo label {} to edit", index))?;
+        double_click(&label)?;
+
+        let doc = document.clone();
+        let Found { found: edit, .. } = wait_for_dom(&document, wait, 1.0, move || {
+            query_selector(&doc, &["li.editing .edit"])
+        })
+        .await
+        .map_err(|elapsed| format!("timed out waiting {}s for the todo editor", elapsed))?;
+
+        let value = format!("Something edited {}", index);
+        let edit_input = edit
+            .clone_as::<web_sys::HtmlInputElement>()
+            .ok_or_else(|| "todo editor is not an input".to_string())?;
+        edit_input.focus().expect("could not focus todo editor");
+        edit_input.set_value(&value);
+        create_todo_method.dispatch_events(&document.clone_as::<Document>().unwrap(), edit_input);
+
+        let doc = document.clone();
+        let Found { .. } = wait_while_dom(&document, wait, 1.0, move || {
+            todo_label_text(&doc, index).as_ref() != Some(&value)
+        })
+        .await
+        .map_err(|elapsed| format!("todo {} was not edited after {}s", index, elapsed))?;
+        cycles.push(perf_now() - cycle_start);
+    }
+    edit_todos_step.cycles = Some(cycles);
+    end_step(&mut edit_todos_step, paint, &perf_now).await?;
+    Ok(edit_todos_step)
+}
+
+/// Return the number of todos in the list that are displayed.
+fn visible_todos(document: &Dom) -> usize {
+    query_selector_all(document, "#todo-list li, .todo-list li")
+        .iter()
+        .filter(|li| {
+            li.clone_as::<HtmlElement>()
+                .map(|el| el.offset_parent().is_some())
+                .unwrap_or(false)
+        })
+        .count()
+}
+
+/// Return the number of todos in the list that are completed.
+fn completed_todos(document: &Dom) -> usize {
+    query_selector_all(document, "#todo-list li.completed, .todo-list li.completed").len()
+}
+
+/// Click the toggles of the first `count` todos, one right after another.
+/// Returns how long each click's handlers took to run, which is the part of
+/// the toggle that happens before the click returns.
+fn click_first_toggles(
+    document: &Dom,
+    count: usize,
+    perf_now: &impl Fn() -> f64,
+) -> Result<Vec<f64>, String> {
+    let mut cycles = vec![];
+    for toggle in query_selector_all(document, ".toggle")
+        .into_iter()
+        .take(count)
+    {
+        let toggle = toggle
+            .clone_as::<HtmlElement>()
+            .ok_or_else(|| "todo toggle is not an HtmlElement".to_string())?;
+        let cycle_start = perf_now();
+        toggle.click();
+        cycles.push(perf_now() - cycle_start);
+    }
+    Ok(cycles)
+}
+
+/// Click the toggles of the first `count` todos and wait until the list has
+/// `expect_completed` completed todos.
+async fn toggle_first_todos(
+    document: &Dom,
+    count: usize,
+    expect_completed: usize,
+    wait: WaitStrategy,
+    perf_now: &impl Fn() -> f64,
+) -> Result<(), String> {
+    click_first_toggles(document, count, perf_now)?;
+    let doc = document.clone();
+    let Found { .. } = wait_while_dom(document, wait, 5.0, move || {
+        completed_todos(&doc) != expect_completed
+    })
+    .await
+    .map_err(|elapsed| {
+        format!(
+            "timed out waiting {}s for {} completed todos",
+            elapsed, expect_completed
+        )
+    })?;
+    Ok(())
+}
+
+/// Complete half of the todos, then time switching the filter to active,
+/// completed and back to all. Each switch is its own step.
+async fn filter_todos(
+    document: Dom,
+    todos: usize,
+    wait: WaitStrategy,
+    paint: Option<&Window>,
+    perf_now: impl Fn() -> f64,
+) -> Result<Vec<BenchmarkStep>, String> {
+    let location = document
+        .clone_as::<Document>()
+        .and_then(|doc| doc.default_view())
+        .ok_or_else(|| "todo document has no window".to_string())?
+        .location();
+    let half = todos / 2;
+    toggle_first_todos(&document, half, half, wait, &perf_now).await?;
+
+    let mut steps = vec![];
+    let filters = [
+        ("filter active", "#/active", todos - half),
+        ("filter completed", "#/completed", half),
+        ("filter all", "#/", todos),
+    ];
+    for (name, hash, expected) in filters.iter() {
+        let mut filter_step = BenchmarkStep::new(name, perf_now());
+        location
+            .set_hash(hash)
+            .map_err(|_| format!("could not route to {}", hash))?;
+        let doc = document.clone();
+        let expected = *expected;
+        let Found { .. } = wait_while_dom(&document, wait, 5.0, move || {
+            visible_todos(&doc) != expected
+        })
+        .await
+        .map_err(|elapsed| {
+            format!(
+                "timed out waiting {}s for {} todos to show at {}",
+                elapsed, expected, hash
+            )
+        })?;
+        end_step(&mut filter_step, paint, &perf_now).await?;
+        steps.push(filter_step);
+    }
+
+    toggle_first_todos(&document, half, 0, wait, &perf_now).await?;
+    Ok(steps)
+}
+
+/// Time completing every todo with one click of the toggle all checkbox, and
+/// then reverting them with another. Each click is its own step.
+async fn toggle_all_todos(
+    document: Dom,
+    todos: usize,
+    wait: WaitStrategy,
+    paint: Option<&Window>,
+    perf_now: impl Fn() -> f64,
+) -> Result<Vec<BenchmarkStep>, String> {
+    let mut steps = vec![];
+    for (name, expected) in [("toggle all completed", todos), ("toggle all active", 0)].iter() {
+        // Some frameworks re-render the checkbox, so look it up every time
+        let toggle_all = query_selector(&document, &["#toggle-all", ".toggle-all"])
+            .ok_or_else(|| "no toggle all checkbox".to_string())?
+            .clone_as::<HtmlElement>()
+            .ok_or_else(|| "toggle all checkbox is not an HtmlElement".to_string())?;
+
+        let mut toggle_all_step = BenchmarkStep::new(name, perf_now());
+        toggle_all.click();
+        let doc = document.clone();
+        let expected = *expected;
+        let Found { .. } = wait_while_dom(&document, wait, 5.0, move || {
+            completed_todos(&doc) != expected
+        })
+        .await
+        .map_err(|elapsed| {
+            format!(
+                "timed out waiting {}s for toggle all to complete {} todos",
+                elapsed, expected
+            )
+        })?;
+        end_step(&mut toggle_all_step, paint, &perf_now).await?;
+        steps.push(toggle_all_step);
+    }
+    Ok(steps)
+}
+
+/// Time clicking the toggles of the first `num_toggles` todos back to back
+/// until the clear completed button shows. Each click is kept as a cycle
+/// without waiting on it, so the step times the same work it always has.
 async fn complete_todos(
     document: Dom,
+    todos: usize,
+    num_toggles: usize,
+    wait: WaitStrategy,
+    paint: Option<&Window>,
     perf_now: impl Fn() -> f64,
 ) -> Result<BenchmarkStep, String> {
-    let mut complete_todos_step = BenchmarkStep {
-        name: "complete todos".to_string(),
-        start: perf_now(),
-        end: None,
-        cycles: None,
-    };
+    let mut complete_todos_step = BenchmarkStep::new("complete todos", perf_now());
     let doc = document.clone();
-    let Found { found: toggles, .. } = wait_for(5.0, move || -> Option<Vec<Dom>> {
-        let elements = query_selector_all(&doc, ".toggle");
-        if elements.len() != 100 {
-            trace!("list size: {}", elements.len());
-            None
-        } else {
-            Some(elements)
-        }
+    let Found { .. } = wait_while_dom(&document, wait, 5.0, move || {
+        let len = query_selector_all(&doc, ".toggle").len();
+        trace!("list size: {}", len);
+        len != todos
     })
     .await
     .map_err(|_| "todos could not be found to complete".to_string())?;
     trace!("  found complete toggles");
-    for input in toggles.into_iter() {
-        input
-            .clone_as::<web_sys::HtmlInputElement>()
-            .unwrap()
-            .click();
-    }
+    let cycles = click_first_toggles(&document, num_toggles.min(todos), &perf_now)?;
+    complete_todos_step.cycles = Some(cycles);
 
-    let Found { .. } = wait_while(5.0, move || {
-        query_selector(&document, &["#clear-completed", ".clear-completed"]).is_none()
+    let doc = document.clone();
+    let Found { .. } = wait_while_dom(&document, wait, 5.0, move || {
+        query_selector(&doc, &["#clear-completed", ".clear-completed"]).is_none()
     })
     .await
     .map_err(|elapsed| {
@@ -262,119 +659,173 @@ async fn complete_todos(
             elapsed
         )
     })?;
-    complete_todos_step.end = Some(perf_now());
+    end_step(&mut complete_todos_step, paint, &perf_now).await?;
     Ok(complete_todos_step)
 }
 
-async fn delete_todos(document: Dom, perf_now: impl Fn() -> f64) -> Result<BenchmarkStep, String> {
+/// Click the first destroy button and wait for the todo to disappear.
+async fn destroy_first_todo(
+    document: &Dom,
+    remaining: usize,
+    wait: WaitStrategy,
+) -> Result<(), String> {
+    let list = query_selector_all(document, ".destroy");
+    if list.len() != remaining {
+        // We are still waiting for the previous one to have disappeared
+        return Err(format!("unexpected number of todos: {}", list.len()));
+    }
+
+    let el: HtmlElement = list
+        .first()
+        .ok_or_else(|| "no destroy button to click".to_string())?
+        .clone_as::<HtmlElement>()
+        .ok_or_else(|| "destroy button is not an HtmlElement".to_string())?;
+    el.click();
+
+    let doc = document.clone();
+    let Found { .. } = wait_while_dom(document, wait, 5.0, move || {
+        let list = query_selector_all(&doc, ".destroy");
+        list.len() != remaining - 1
+    })
+    .await
+    .map_err(|elapsed| format!("couldn't confirm todo deleted after {} seconds", elapsed))?;
+    Ok(())
+}
+
+/// Time destroying the first `destroys` todos one at a time, then clearing
+/// the completed ones. Todos that weren't completed, when the complete step
+/// doesn't run, are destroyed in a step of their own, so "delete todos" does
+/// the same work whichever steps ran before it.
+async fn delete_todos(
+    document: Dom,
+    todos: usize,
+    destroys: usize,
+    expect_completed: usize,
+    wait: WaitStrategy,
+    paint: Option<&Window>,
+    perf_now: impl Fn() -> f64,
+) -> Result<Vec<BenchmarkStep>, String> {
     // Find the destroy toggle
     // Some frameworks are weird and re-use elements so we can't simply iterate
     // over all the destroy toggles - instead we have to get the first destroy
     // toggle and delete it, confirm it and continue...
     //
-    // First assert that our list is 100 elements
+    // First assert that our list is the expected length
     let doc = document.clone();
-    let Found { .. } = wait_while(1.0, move || {
+    let Found { .. } = wait_while_dom(&document, wait, 1.0, move || {
         let toggles = query_selector_all(&doc, ".destroy");
-        toggles.len() != 100
+        toggles.len() != todos
     })
     .await
     .map_err(|_| "could not confirm destroy toggles exist".to_string())?;
 
-    let mut delete_todos_step = BenchmarkStep {
-        name: "delete todos".to_string(),
-        start: perf_now(),
-        end: None,
-        cycles: None,
-    };
-    let mut deletions_remaining = 100;
-    let manual_delete_len = 10;
-    'destroy_todos: loop {
-        trace!("  {}", deletions_remaining);
-        {
-            let list = query_selector_all(&document, ".destroy");
-            if list.len() != deletions_remaining {
-                // We are still waiting for the previous one to have disappeared
-                return Err(format!(
-                    "unexpected number of todos: {}",
-                    deletions_remaining
-                ));
-            }
-
-            let el: HtmlElement = list.first()
-                .ok_or_else(|| "no destroy button to click".to_string())?
-                .clone_as::<HtmlElement>()
-                .ok_or_else(|| "destroy button is not an HtmlElement".to_string())?;
-            el.click();
-        }
-
-        deletions_remaining -= 1;
-
-        let doc = document.clone();
-        let Found { .. } = wait_while(5.0, move || {
-            let list = query_selector_all(&doc, ".destroy");
-            list.len() != deletions_remaining
-        })
-        .await
-        .map_err(|elapsed| format!("couldn't confirm todo deleted after {} seconds", elapsed))?;
-
-        if deletions_remaining <= 100 - manual_delete_len {
-            break 'destroy_todos;
-        }
+    let mut delete_todos_step = BenchmarkStep::new("delete todos", perf_now());
+    let mut cycles = vec![];
+    let mut remaining = todos;
+    while remaining > todos - destroys.min(todos) {
+        trace!("  {}", remaining);
+        let cycle_start = perf_now();
+        destroy_first_todo(&document, remaining, wait).await?;
+        cycles.push(perf_now() - cycle_start);
+        remaining -= 1;
     }
 
     let _ = wait_secs(0.5).await;
-    clear_completed_todos(document.clone()).await?;
-
-    let num_destroy_toggles = query_selector_all(&document, ".destroy").len();
-    if num_destroy_toggles > 0 {
-        return Err(format!("there are {} remaining todos", num_destroy_toggles));
+    let completed = completed_todos(&document);
+    if completed != expect_completed {
+        log::warn!(
+            "expected {} completed todos to clear but there are {}",
+            expect_completed,
+            completed
+        );
     }
+    clear_completed_todos(document.clone(), wait).await?;
+    delete_todos_step.cycles = Some(cycles);
+    end_step(&mut delete_todos_step, paint, &perf_now).await?;
+    let mut steps = vec![delete_todos_step];
 
-    delete_todos_step.end = Some(perf_now());
-    Ok(delete_todos_step)
+    // Anything left wasn't toggled, so it has to go one at a time
+    if !query_selector_all(&document, ".destroy").is_empty() {
+        let mut destroy_step = BenchmarkStep::new("destroy remaining todos", perf_now());
+        destroy_step.cycles = Some(destroy_remaining_todos(&document, wait, &perf_now).await?);
+        end_step(&mut destroy_step, paint, &perf_now).await?;
+        steps.push(destroy_step);
+    }
+    Ok(steps)
 }
 
-async fn clear_completed_todos(document: Dom) -> Result<(), String> {
-    if let Some(clear_button) = query_selector(&document, &["#clear-completed", ".clear-completed"]) {
-        clear_button
-            .clone_as::<HtmlElement>()
-            .ok_or_else(|| "clear completed todos button is not an element".to_string())?
-            .click();
-
-        let Found { .. } = wait_while(5.0, move || query_selector_all(&document, ".destroy").len() > 0)
-        .await
-        .map_err(|elapsed| format!("timed out ({}s) while clearing existing todos", elapsed))?;
-    } else {
-        let num_todos = query_selector_all(&document, ".destroy").len();
-        if num_todos > 0 {
-            log::error!(
-                "there are {} todos but no clear completed button",
-                num_todos
-            );
-        }
+/// Click the clear completed button if any todos are completed, and wait
+/// until none are. Some frameworks keep the button around with nothing to
+/// clear, so it's only clicked when there's something to clear.
+async fn clear_completed_todos(document: Dom, wait: WaitStrategy) -> Result<(), String> {
+    if completed_todos(&document) == 0 {
+        return Ok(());
     }
+    let clear_button = match query_selector(&document, &["#clear-completed", ".clear-completed"]) {
+        Some(clear_button) => clear_button,
+        None => {
+            log::error!("there are completed todos but no clear completed button");
+            return Ok(());
+        }
+    };
+    clear_button
+        .clone_as::<HtmlElement>()
+        .ok_or_else(|| "clear completed todos button is not an element".to_string())?
+        .click();
 
+    let doc = document.clone();
+    let Found { .. } = wait_while_dom(&document, wait, 5.0, move || completed_todos(&doc) > 0)
+        .await
+        .map_err(|elapsed| format!("timed out ({}s) while clearing completed todos", elapsed))?;
     Ok(())
 }
 
+/// Destroy the todos left one at a time. Returns how long each took.
+async fn destroy_remaining_todos(
+    document: &Dom,
+    wait: WaitStrategy,
+    perf_now: &impl Fn() -> f64,
+) -> Result<Vec<f64>, String> {
+    let mut cycles = vec![];
+    let mut remaining = query_selector_all(document, ".destroy").len();
+    while remaining > 0 {
+        let cycle_start = perf_now();
+        destroy_first_todo(document, remaining, wait).await?;
+        cycles.push(perf_now() - cycle_start);
+        remaining -= 1;
+    }
+    Ok(cycles)
+}
+
 async fn execute_bench(
     framework: FrameworkCard,
+    config: BenchConfig,
     iframe: Dom,
     tx: broadcast::Sender<ViewMsg>,
     src: String,
-) -> Result<Vec<BenchmarkStep>, String> {
-    let mut steps = vec![];
+    benchmark: &mut Benchmark,
+) -> Result<(), String> {
+    let steps = &mut benchmark.steps;
     let bench_start = mogwai::utils::window()
         .performance()
         .ok_or_else(|| "no performance object".to_string())?
         .now();
     let perf_now = move || mogwai::utils::window().performance().unwrap().now() - bench_start;
 
+    let iframe_window = iframe
+        .clone_as::<HtmlIFrameElement>()
+        .and_then(|iframe| iframe.content_window())
+        .ok_or_else(|| "iframe has no window".to_string())?;
+    let paint = if config.settle {
+        Some(&iframe_window)
+    } else {
+        None
+    };
+
     // Load the iframe source
     trace!("{} waiting for iframe load complete", src);
 
-    let some_steps = load_step(iframe.clone(), tx, src, perf_now.clone()).await?;
+    let some_steps = load_step(iframe.clone(), tx, src, paint, perf_now.clone()).await?;
     steps.push(some_steps);
     trace!("  load complete");
     let document = iframe
@@ -388,38 +839,117 @@ async fn execute_bench(
         .expect("no iframe content_document");
 
     trace!("finding todo input");
-    let (input, step) = find_todo_input(document.clone(), perf_now.clone()).await?;
+    let (input, step) =
+        find_todo_input(document.clone(), config.wait, paint, perf_now.clone()).await?;
     steps.push(step);
     trace!("  found todo input");
 
+    // Wasm apps fetch their module after the load event, so we wait for the
+    // app to be up before totaling what was downloaded.
+    benchmark.sizes = Some(resource_sizes(&iframe)?);
+
     if framework.wait_for_input_focus {
         trace!("waiting for todo focus");
-        steps.push(wait_todo_focus(input.clone(), perf_now.clone()).await?);
+        steps.push(wait_todo_focus(input.clone(), paint, perf_now.clone()).await?);
         trace!("  todo is focused");
     }
 
     trace!("creating todos");
-    clear_completed_todos(document.clone()).await?;
+    // Clear out any todos the framework kept from an earlier run
+    clear_completed_todos(document.clone(), config.wait).await?;
+    destroy_remaining_todos(&document, config.wait, &perf_now).await?;
 
     steps.push(
         create_todos(
             document.clone(),
             input.clone(),
-            framework.create_todo_method,
+            framework.create_todo_method.clone(),
+            config.todos,
+            config.wait,
+            paint,
             perf_now.clone(),
         )
         .await?,
     );
     trace!("  created todos");
 
-    trace!("completing todos");
-    steps.push(complete_todos(document.clone(), perf_now.clone()).await?);
-    trace!("  completed/toggled todos");
+    if config.scenarios.edit {
+        trace!("editing todos");
+        steps.push(
+            edit_todos(
+                document.clone(),
+                framework.create_todo_method.clone(),
+                config.edits.min(config.todos),
+                config.wait,
+                paint,
+                perf_now.clone(),
+            )
+            .await?,
+        );
+        trace!("  edited todos");
+    }
+
+    if config.scenarios.filter {
+        trace!("filtering todos");
+        steps.extend(
+            filter_todos(
+                document.clone(),
+                config.todos,
+                config.wait,
+                paint,
+                perf_now.clone(),
+            )
+            .await?,
+        );
+        trace!("  filtered todos");
+    }
+
+    if config.scenarios.toggle_all {
+        trace!("toggling all todos");
+        steps.extend(
+            toggle_all_todos(
+                document.clone(),
+                config.todos,
+                config.wait,
+                paint,
+                perf_now.clone(),
+            )
+            .await?,
+        );
+        trace!("  toggled all todos");
+    }
+
+    if config.scenarios.complete {
+        trace!("completing todos");
+        steps.push(
+            complete_todos(
+                document.clone(),
+                config.todos,
+                config.toggles,
+                config.wait,
+                paint,
+                perf_now.clone(),
+            )
+            .await?,
+        );
+        trace!("  completed/toggled todos");
+    }
 
     trace!("deleting todos");
-    steps.push(delete_todos(document.clone(), perf_now.clone()).await?);
+    steps.extend(
+        delete_todos(
+            document.clone(),
+            config.todos,
+            config.destroys,
+            config.completed_at_clear(),
+            config.wait,
+            paint,
+            perf_now.clone(),
+        )
+        .await?,
+    );
     trace!("  confirmed destroyed todos");
-    Ok(steps)
+    Ok(())
 }
 
 /// Handles running the benchmarks for one framework step by step
@@ -431,7 +961,11 @@ async fn bench_runner_logic(
     let iframe = rx_iframe.recv().await.unwrap();
     loop {
         match rx_logic.next().await {
-            Some(Run { framework, reply }) => {
+            Some(Run {
+                framework,
+                config,
+                reply,
+            }) => {
                 trace!("running {}", framework.name);
 
                 let mut benchmark = Benchmark::new();
@@ -441,15 +975,18 @@ async fn bench_runner_logic(
                 let url = framework.url.clone();
                 tx.broadcast(ViewMsg::StepDisabled(true)).await.unwrap();
 
-                let res = execute_bench(framework.clone(), iframe.clone(), tx.clone(), url).await;
-                match res {
-                    Ok(steps) => {
-                        benchmark.steps.extend(steps);
-                    }
-                    Err(err) => {
-                        error!("{}", err);
-                        benchmark.failed_message = Some(err.clone());
-                    }
+                let res = execute_bench(
+                    framework.clone(),
+                    config,
+                    iframe.clone(),
+                    tx.clone(),
+                    url,
+                    &mut benchmark,
+                )
+                .await;
+                if let Err(err) = res {
+                    error!("{}", err);
+                    benchmark.failed_message = Some(err.clone());
                 }
 
                 trace!("bench completed");
@@ -494,11 +1031,12 @@ impl BenchRunnerFacade {
         (BenchRunnerFacade { tx_logic }, component)
     }
 
-    pub async fn run(&self, framework: FrameworkCard) -> Benchmark {
+    pub async fn run(&self, framework: FrameworkCard, config: BenchConfig) -> Benchmark {
         let (tx, mut rx) = broadcast::bounded(1);
         self.tx_logic
             .broadcast(Run {
                 framework,
+                config,
                 reply: tx,
             })
             .await
@@ -506,3 +1044,18 @@ impl BenchRunnerFacade {
         rx.next().await.unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_without_complete_leave_nothing_to_clear() {
+        let mut config = BenchConfig::with_todos(100);
+        // A tenth is destroyed one at a time before the rest are cleared
+        assert_eq!(config.completed_at_clear(), 90);
+
+        config.scenarios.complete = false;
+        assert_eq!(config.completed_at_clear(), 0);
+    }
+}
@@ -0,0 +1,205 @@
+//! Tests whether two frameworks' step times really differ.
+//!
+//! Each step's samples, one per run, are compared with a Mann-Whitney U test,
+//! which doesn't assume step times are normally distributed. Step times
+//! usually aren't - they have a long tail of GC pauses and layout thrashing.
+use mogwai::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlSelectElement;
+
+use super::{
+    bench_runner::Benchmark,
+    graph::{self, GraphableBenchmark},
+    stats::{self, Summary},
+};
+
+#[derive(Clone)]
+enum In {
+    SelectA(String),
+    SelectB(String),
+}
+
+impl In {
+    fn selected_name(event: Event) -> Option<String> {
+        let select = event.target()?.dyn_into::<HtmlSelectElement>().ok()?;
+        Some(select.value())
+    }
+}
+
+/// The samples of each step both frameworks ran, plus their total times.
+fn paired_samples(
+    a: &GraphableBenchmark,
+    b: &GraphableBenchmark,
+) -> Vec<(String, Vec<f64>, Vec<f64>)> {
+    let mut samples = a
+        .data
+        .iter()
+        .filter_map(|datum_a| {
+            let datum_b = b.data.iter().find(|datum| datum.name == datum_a.name)?;
+            Some((
+                datum_a.name.clone(),
+                datum_a.durations(),
+                datum_b.durations(),
+            ))
+        })
+        .collect::<Vec<_>>();
+    samples.push(("total".into(), a.totals.clone(), b.totals.clone()));
+    samples
+}
+
+fn comparison_row(a: &str, b: &str, step: String, samples_a: &[f64], samples_b: &[f64]) -> Dom {
+    let median = |samples: &[f64]| {
+        Summary::from_samples(samples)
+            .map(|summary| format!("{:.1}ms", summary.median))
+            .unwrap_or_else(|| "???".to_string())
+    };
+    let (class, p, effect_size, verdict) = match stats::mann_whitney_u(samples_a, samples_b) {
+        Some(test) if test.is_significant() => (
+            "table-success",
+            format!("{:.3}", test.p),
+            format!("{:+.2}", test.effect_size),
+            format!("{} is faster", if test.effect_size < 0.0 { a } else { b }),
+        ),
+        Some(test) => (
+            "",
+            format!("{:.3}", test.p),
+            format!("{:+.2}", test.effect_size),
+            "not significant".to_string(),
+        ),
+        None => ("", "???".into(), "???".into(), "no samples".into()),
+    };
+    let row = builder! {
+        <tr class=class>
+            <td>{step}</td>
+            <td>{median(samples_a)}</td>
+            <td>{median(samples_b)}</td>
+            <td>{p}</td>
+            <td>{effect_size}</td>
+            <td>{verdict}</td>
+        </tr>
+    };
+    Component::from(row).build().unwrap().into_inner()
+}
+
+async fn logic(
+    frameworks: Vec<GraphableBenchmark>,
+    mut a: String,
+    mut b: String,
+    mut rx_logic: broadcast::Receiver<In>,
+    rx_body: mpmc::Receiver<Dom>,
+) {
+    let body = rx_body.recv().await.unwrap();
+    loop {
+        let find = |name: &str| frameworks.iter().find(|framework| framework.name == name);
+        let rows = match (find(&a), find(&b)) {
+            (Some(framework_a), Some(framework_b)) => paired_samples(framework_a, framework_b)
+                .into_iter()
+                .map(|(step, samples_a, samples_b)| {
+                    comparison_row(&a, &b, step, &samples_a, &samples_b)
+                })
+                .collect::<Vec<_>>(),
+            _ => vec![],
+        };
+        body.patch_children(ListPatch::splice(.., rows.into_iter()))
+            .unwrap();
+
+        match rx_logic.next().await {
+            Some(In::SelectA(name)) => a = name,
+            Some(In::SelectB(name)) => b = name,
+            None => break,
+        }
+    }
+}
+
+fn framework_options(names: &[String], selected: &str) -> Vec<ViewBuilder<Dom>> {
+    names
+        .iter()
+        .map(|name| {
+            builder! {
+                <option value=name boolean:selected=name == selected>{name}</option>
+            }
+        })
+        .collect()
+}
+
+fn view(
+    names: &[String],
+    a: &str,
+    b: &str,
+    tx: broadcast::Sender<In>,
+    tx_body: mpmc::Sender<Dom>,
+) -> ViewBuilder<Dom> {
+    builder! {
+        <div class="mb-4">
+            <div class="form-row mb-2">
+                <div class="input-group col-4">
+                    <div class="input-group-prepend">
+                        <span class="input-group-text">"compare"</span>
+                    </div>
+                    <select
+                     class="custom-select"
+                     on:change = tx.sink().contra_filter_map(|event: Event| {
+                         In::selected_name(event).map(In::SelectA)
+                     })>
+                        {framework_options(names, a)}
+                    </select>
+                </div>
+                <div class="input-group col-4">
+                    <div class="input-group-prepend">
+                        <span class="input-group-text">"with"</span>
+                    </div>
+                    <select
+                     class="custom-select"
+                     on:change = tx.sink().contra_filter_map(|event: Event| {
+                         In::selected_name(event).map(In::SelectB)
+                     })>
+                        {framework_options(names, b)}
+                    </select>
+                </div>
+            </div>
+            <table class="table table-sm">
+                <thead>
+                    <tr>
+                        <th scope="col">"Step"</th>
+                        <th scope="col">"Median"</th>
+                        <th scope="col">"Median with"</th>
+                        <th scope="col" title="two-sided Mann-Whitney U test">"p"</th>
+                        <th
+                         scope="col"
+                         title="rank-biserial correlation, negative when the first is faster">
+                            "Effect size"
+                        </th>
+                        <th scope="col" title=format!("significant when p < {}", stats::SIGNIFICANCE)>
+                            "Verdict"
+                        </th>
+                    </tr>
+                </thead>
+                <tbody post:build = move |dom: &mut Dom| tx_body.try_send(dom.clone()).unwrap()>
+                </tbody>
+            </table>
+        </div>
+    }
+}
+
+/// A view comparing any two of the frameworks in the given benchmarks, or
+/// `None` if fewer than two frameworks have samples.
+pub fn comparison(benchmarks: &Vec<Benchmark>) -> Option<Component<Dom>> {
+    let mut frameworks = graph::process_benchmarks(benchmarks)
+        .into_iter()
+        .filter(|framework| !framework.data.is_empty())
+        .collect::<Vec<_>>();
+    frameworks.sort_by(|a, b| a.name.cmp(&b.name));
+    let names = frameworks
+        .iter()
+        .map(|framework| framework.name.clone())
+        .collect::<Vec<_>>();
+    let a = names.get(0)?.clone();
+    let b = names.get(1)?.clone();
+
+    let (tx_logic, rx_logic) = broadcast::bounded(1);
+    let (tx_body, rx_body) = mpmc::bounded(1);
+    Some(
+        Component::from(view(&names, &a, &b, tx_logic, tx_body))
+            .with_logic(logic(frameworks, a, b, rx_logic, rx_body)),
+    )
+}
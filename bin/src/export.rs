@@ -0,0 +1,45 @@
+//! Exports benchmarks as JSON or CSV downloads, and imports exported JSON.
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+
+pub use todo_mvc_bench_core::export::{from_json, to_csv, to_json};
+
+/// How long the download url is kept around after the click, long enough for
+/// the browser to have started reading it.
+const REVOKE_DELAY_MS: i32 = 10_000;
+
+/// Make the browser download `contents` as a file.
+pub fn download(filename: &str, mime_type: &str, contents: &str) -> Result<(), JsValue> {
+    let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+    let mut options = BlobPropertyBag::new();
+    options.type_(mime_type);
+    let blob = Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+
+    // Firefox ignores clicks on anchors that aren't in the document
+    let document = mogwai::utils::document();
+    let body = document
+        .body()
+        .ok_or_else(|| JsValue::from_str("the document has no body"))?;
+    let anchor = document
+        .create_element("a")?
+        .dyn_into::<HtmlAnchorElement>()?;
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+    body.append_child(&anchor)?;
+    anchor.click();
+    body.remove_child(&anchor)?;
+
+    // Revoking the url right away can cancel the download
+    let revoke = Closure::once_into_js(move || {
+        if let Err(err) = Url::revoke_object_url(&url) {
+            log::error!("could not revoke the download url: {:?}", err);
+        }
+    });
+    mogwai::utils::window()
+        .set_timeout_with_callback_and_timeout_and_arguments_0(
+            revoke.unchecked_ref(),
+            REVOKE_DELAY_MS,
+        )
+        .map(|_| ())
+}
@@ -2,8 +2,14 @@ use std::sync::Arc;
 
 use log::trace;
 use mogwai::{lock::RwLock, prelude::*};
+use serde::{Deserialize, Serialize};
 use web_sys::{Document, KeyboardEvent, KeyboardEventInit};
 
+use super::{
+    bench_runner::{ResourceSize, ResourceSizes},
+    stats::Summary,
+};
+
 #[derive(Clone, Debug)]
 pub enum FrameworkState {
     Ready,
@@ -12,7 +18,8 @@ pub enum FrameworkState {
     Erred(String),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CreateTodoMethod {
     Change,
     InputAndKeypress,
@@ -98,6 +105,18 @@ impl FrameworkFacade {
             .unwrap();
     }
 
+    pub async fn set_sizes(&self, sizes: Option<ResourceSizes>) {
+        self.tx_logic.broadcast(In::SetSizes(sizes)).await.unwrap();
+    }
+
+    pub async fn set_score(&self, score: Option<f64>) {
+        self.tx_logic.broadcast(In::SetScore(score)).await.unwrap();
+    }
+
+    pub async fn set_total(&self, total: Option<Summary>) {
+        self.tx_logic.broadcast(In::SetTotal(total)).await.unwrap();
+    }
+
     pub async fn get_card(&self) -> FrameworkCard {
         let (tx, mut rx) = broadcast::bounded(1);
         self.tx_logic.broadcast(In::QueryCard(tx)).await.unwrap();
@@ -132,6 +151,9 @@ pub enum In {
     ChangeState(FrameworkState),
     ToggleEnabled,
     IsEnabled(bool),
+    SetSizes(Option<ResourceSizes>),
+    SetScore(Option<f64>),
+    SetTotal(Option<Summary>),
     QueryCard(broadcast::Sender<FrameworkCard>),
 }
 
@@ -139,6 +161,9 @@ pub enum In {
 pub enum Out {
     ChangeState(FrameworkState),
     IsEnabled(bool),
+    Sizes(Option<ResourceSizes>),
+    Score(Option<f64>),
+    Total(Option<Summary>),
 }
 
 fn toggle_btn_class(enabled: bool) -> String {
@@ -150,7 +175,107 @@ fn toggle_btn_class(enabled: bool) -> String {
     .to_string()
 }
 
+fn format_bytes(bytes: f64) -> String {
+    if bytes < 1024.0 {
+        format!("{}B", bytes)
+    } else if bytes < 1024.0 * 1024.0 {
+        format!("{:.1}kB", bytes / 1024.0)
+    } else {
+        format!("{:.2}MB", bytes / (1024.0 * 1024.0))
+    }
+}
+
 impl Out {
+    fn sizes_text(&self) -> Option<String> {
+        if let Out::Sizes(sizes) = self {
+            Some(
+                sizes
+                    .as_ref()
+                    .map(|sizes| {
+                        let total = sizes.total();
+                        format!(
+                            "{} / {}",
+                            format_bytes(total.encoded),
+                            format_bytes(total.decoded)
+                        )
+                    })
+                    .unwrap_or_else(|| "???".to_string()),
+            )
+        } else {
+            None
+        }
+    }
+
+    fn sizes_title(&self) -> Option<String> {
+        if let Out::Sizes(sizes) = self {
+            let sizes = match sizes {
+                Some(sizes) => sizes,
+                None => return Some(String::new()),
+            };
+            let kind = |name: &str, size: &ResourceSize| {
+                format!(
+                    "{}: {} compressed, {} uncompressed",
+                    name,
+                    format_bytes(size.encoded),
+                    format_bytes(size.decoded)
+                )
+            };
+            Some(
+                vec![
+                    kind("js", &sizes.js),
+                    kind("wasm", &sizes.wasm),
+                    kind("css", &sizes.css),
+                ]
+                .join("\n"),
+            )
+        } else {
+            None
+        }
+    }
+
+    fn score_text(&self) -> Option<String> {
+        if let Out::Score(score) = self {
+            Some(
+                score
+                    .map(|score| format!("{:.2}", score))
+                    .unwrap_or_else(|| "???".to_string()),
+            )
+        } else {
+            None
+        }
+    }
+
+    fn total_text(&self) -> Option<String> {
+        if let Out::Total(total) = self {
+            Some(
+                total
+                    .map(|total| {
+                        // The confidence interval is around the mean
+                        if total.n > 1 {
+                            format!(
+                                "{}ms ±{}",
+                                total.mean.round() as u32,
+                                total.ci95_half_width().round() as u32
+                            )
+                        } else {
+                            format!("{}ms", total.mean.round() as u32)
+                        }
+                    })
+                    .unwrap_or_else(|| "???".to_string()),
+            )
+        } else {
+            None
+        }
+    }
+
+    fn total_title(&self) -> Option<String> {
+        if let Out::Total(total) = self {
+            Some(total.map(|total| total.to_string()).unwrap_or_default())
+        } else {
+            None
+        }
+    }
+
     fn error_state_msg(&self) -> Option<Option<String>> {
         if let Out::ChangeState(FrameworkState::Erred(msg)) = self {
             Some(Some(msg.clone()))
@@ -202,6 +327,15 @@ async fn logic(
                     .await
                     .unwrap();
             }
+            In::SetSizes(sizes) => {
+                tx_view.broadcast(Out::Sizes(sizes)).await.unwrap();
+            }
+            In::SetScore(score) => {
+                tx_view.broadcast(Out::Score(score)).await.unwrap();
+            }
+            In::SetTotal(total) => {
+                tx_view.broadcast(Out::Total(total)).await.unwrap();
+            }
         }
     }
 }
@@ -271,8 +405,15 @@ fn view(
                  .unwrap()
                 }
             </td>
-            <td>"???"</td>
-            <td>"???"</td>
+            <td title=("", rx.clone().filter_map(|msg| async move { msg.sizes_title() }))>
+                {("???", rx.clone().filter_map(|msg| async move { msg.sizes_text() }))}
+            </td>
+            <td title="geometric mean of step times relative to the fastest, lower is better">
+                {("???", rx.clone().filter_map(|msg| async move { msg.score_text() }))}
+            </td>
+            <td title=("", rx.clone().filter_map(|msg| async move { msg.total_title() }))>
+                {("???", rx.clone().filter_map(|msg| async move { msg.total_text() }))}
+            </td>
             <td>
                 <dd class="col-sm-12">
                     {(
@@ -287,281 +428,3 @@ fn view(
         </tr>
     }
 }
-
-pub fn all_cards() -> Vec<FrameworkCard> {
-    vec![
-        FrameworkCard {
-            name: "mogwai 0.1".into(),
-            url: "frameworks/mogwai-0.1/index.html".into(),
-            attributes: vec![
-                ("language".into(), "rust".into()),
-                ("version".into(), "0.1.5".into()),
-                ("has vdom".into(), "no".into()),
-            ],
-            is_enabled: true,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::Change,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "mogwai 0.2".into(),
-            url: "frameworks/mogwai-0.2/index.html".into(),
-            attributes: vec![
-                ("language".into(), "rust".into()),
-                ("version".into(), "0.2.0".into()),
-                ("has vdom".into(), "no".into()),
-            ],
-            is_enabled: true,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::Change,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "mogwai 0.5".into(),
-            url: "frameworks/mogwai-0.5/index.html".into(),
-            attributes: vec![
-                ("language".into(), "rust".into()),
-                ("version".into(), "0.2.0".into()),
-                ("has vdom".into(), "no".into()),
-            ],
-            is_enabled: true,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::Change,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "sauron".into(),
-            url: "frameworks/sauron/index.html".into(),
-            attributes: vec![
-                ("language".into(), "rust".into()),
-                ("version".into(), "0.20.3".into()),
-                ("has vdom".into(), "yes".into()),
-            ],
-            is_enabled: true,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::InputAndKeypress,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "yew".into(),
-            url: "frameworks/yew-0.10/index.html".into(),
-            attributes: vec![
-                ("language".into(), "rust".into()),
-                ("version".into(), "0.10.0".into()),
-                ("has vdom".into(), "yes".into()),
-            ],
-            is_enabled: true,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::InputAndKeypress,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "Backbone".into(),
-            url: "frameworks/backbone/index.html".into(),
-            attributes: vec![
-                ("language".into(), "javascript".into()),
-                ("version".into(), "1.1.2".into()),
-                ("has vdom".into(), "no".into()),
-            ],
-            is_enabled: true,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::InputAndKeypress,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "Asterius".into(),
-            url: "frameworks/asterius/index.html".into(),
-            attributes: vec![
-                ("language".into(), "haskell".into()),
-                ("version".into(), "0".into()),
-                ("has vdom".into(), "no".into()),
-            ],
-            is_enabled: false,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::InputAndKeypress,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "Ember".into(),
-            url: "frameworks/emberjs/index.html".into(),
-            attributes: vec![
-                ("language".into(), "javascript".into()),
-                ("version".into(), "1.4".into()),
-                ("has vdom".into(), "?".into()),
-            ],
-            is_enabled: true,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::InputAndKeyup,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "Angular".into(),
-            url: "frameworks/angularjs-perf/index.html".into(),
-            attributes: vec![
-                ("language".into(), "javascript".into()),
-                ("version".into(), "1.5.3".into()),
-                ("has vdom".into(), "no".into()),
-            ],
-            is_enabled: true,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::Submit,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "Mithril".into(),
-            url: "frameworks/mithril/index.html".into(),
-            attributes: vec![
-                ("language".into(), "javascript".into()),
-                ("version".into(), "0.1.0".into()),
-                ("has vdom".into(), "yes".into()),
-            ],
-            is_enabled: true,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::InputAndKeypress,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "Mithril2".into(),
-            url: "frameworks/mithril-2/index.html".into(),
-            attributes: vec![
-                ("language".into(), "javascript".into()),
-                ("version".into(), "2.0.4".into()),
-                ("has vdom".into(), "yes".into()),
-            ],
-            is_enabled: true,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::InputAndKeypress,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "Elm".into(),
-            url: "frameworks/elm17/index.html".into(),
-            attributes: vec![
-                ("language".into(), "elm".into()),
-                ("version".into(), "0.17".into()),
-                ("has vdom".into(), "yes".into()),
-            ],
-            is_enabled: true,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::InputAndKeydown,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "Preact".into(),
-            url: "frameworks/preact/index.html".into(),
-            attributes: vec![
-                ("language".into(), "javascript".into()),
-                ("version".into(), "8.1.0".into()),
-                ("has vdom".into(), "yes".into()),
-            ],
-            is_enabled: true,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::InputAndKeydown,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "vanilla".into(),
-            url: "frameworks/vanilla-es6/index.html".into(),
-            attributes: vec![
-                ("language".into(), "javascript".into()),
-                ("version".into(), "none".into()),
-                ("has vdom".into(), "no".into()),
-            ],
-            is_enabled: false,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::InputAndKeydown,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "Ractive".into(),
-            url: "frameworks/ractive/index.html".into(),
-            attributes: vec![
-                ("language".into(), "javascript".into()),
-                ("version".into(), "0.3.9".into()),
-                ("has vdom".into(), "yes".into()),
-            ],
-            is_enabled: true,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::InputAndKeydown,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "Knockout".into(),
-            url: "frameworks/knockoutjs/index.html".into(),
-            attributes: vec![
-                ("language".into(), "javascript".into()),
-                ("version".into(), "3.1.0".into()),
-                ("has vdom".into(), "no".into()),
-            ],
-            is_enabled: false,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::InputAndKeydown,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "Vue".into(),
-            url: "frameworks/vue/index.html".into(),
-            attributes: vec![
-                ("language".into(), "javascript".into()),
-                ("version".into(), "1.0.24".into()),
-                ("has vdom".into(), "yes".into()),
-            ],
-            is_enabled: false,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::Change,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "Mercury".into(),
-            url: "frameworks/mercury/index.html".into(),
-            attributes: vec![
-                ("language".into(), "javascript".into()),
-                ("version".into(), "3.1.7".into()),
-                ("has vdom".into(), "yes".into()),
-            ],
-            is_enabled: true,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::InputAndKeydown,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "React".into(),
-            url: "frameworks/react/index.html".into(),
-            attributes: vec![
-                ("language".into(), "javascript".into()),
-                ("version".into(), "15.0.2".into()),
-                ("has vdom".into(), "yes".into()),
-            ],
-            is_enabled: true,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::InputAndKeydown,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "Om".into(),
-            url: "frameworks/om/index.html".into(),
-            attributes: vec![
-                ("language".into(), "clojurescript".into()),
-                ("version".into(), "0.5".into()),
-                ("has vdom".into(), "yes".into()),
-            ],
-            is_enabled: true,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::InputAndKeydown,
-            wait_for_input_focus: false,
-        },
-        FrameworkCard {
-            name: "choo".into(),
-            url: "frameworks/choo/index.html".into(),
-            attributes: vec![
-                ("language".into(), "javascript".into()),
-                ("version".into(), "1.3.0".into()),
-                ("no vdom".into(), "still diffs".into()),
-            ],
-            is_enabled: false,
-            state: FrameworkState::Ready,
-            create_todo_method: CreateTodoMethod::InputAndKeydown,
-            wait_for_input_focus: false,
-        },
-    ]
-}
@@ -2,11 +2,45 @@ use mogwai::prelude::*;
 use std::{collections::HashMap, convert::TryFrom};
 use web_sys::{SvgElement, SvgsvgElement};
 
-use super::bench_runner::{Benchmark, BenchmarkStep};
+use super::{
+    bench_runner::{Benchmark, BenchmarkStep},
+    stats::{BoxPlot, Percentiles, Summary},
+};
+
+pub const SVGNS: &'static str = "http://www.w3.org/2000/svg";
+
+/// Colors that are easy to tell apart, for charts with a color per framework.
+/// Strong colors first, so a few series stay easy to tell apart, then their
+/// lighter shades and some darker ones, enough for every framework.
+const PALETTE: [&str; 24] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+    "#bcbd22", "#17becf", "#aec7e8", "#ffbb78", "#98df8a", "#ff9896", "#c5b0d5", "#c49c94",
+    "#f7b6d2", "#c7c7c7", "#dbdb8d", "#9edae5", "#393b79", "#637939", "#8c6d31", "#843c39",
+];
+
+pub fn palette_color(index: usize) -> &'static str {
+    PALETTE[index % PALETTE.len()]
+}
 
-const SVGNS: &'static str = "http://www.w3.org/2000/svg";
+/// How the graph draws each framework's steps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GraphMode {
+    /// Each step over the span of the run it took, all in one lane.
+    Timeline,
+    /// One bar per framework made of each step's duration end to end, colored
+    /// by step.
+    Stacked,
+    /// The spread of each framework's total and step times over its runs.
+    Box,
+}
 
-fn lang_color(lang: Option<&String>) -> &str {
+impl Default for GraphMode {
+    fn default() -> Self {
+        GraphMode::Timeline
+    }
+}
+
+fn lang_color(lang: Option<&String>) -> &'static str {
     let lang: Option<&str> = lang.as_ref().map(|s| s.as_str());
     match lang {
         Some("rust") => "darkorange",
@@ -19,9 +53,10 @@ fn lang_color(lang: Option<&String>) -> &str {
 }
 
 #[derive(Debug)]
-struct BenchmarkDatum {
-    name: String,
+pub struct BenchmarkDatum {
+    pub name: String,
     points: Vec<(f64, f64)>,
+    cycles: Vec<f64>,
 }
 
 impl BenchmarkDatum {
@@ -32,6 +67,20 @@ impl BenchmarkDatum {
             .fold((0.0, 0.0), |(start, end), (s, e)| (start + s, end + e));
         (s / self.points.len() as f64, e / self.points.len() as f64)
     }
+
+    /// The duration of this step in each run.
+    pub fn durations(&self) -> Vec<f64> {
+        self.points.iter().map(|(start, end)| end - start).collect()
+    }
+
+    fn summary(&self) -> Option<Summary> {
+        Summary::from_samples(&self.durations())
+    }
+
+    fn average_duration(&self) -> f64 {
+        let (start, end) = self.average_span();
+        end - start
+    }
 }
 
 impl TryFrom<&BenchmarkStep> for BenchmarkDatum {
@@ -42,16 +91,19 @@ impl TryFrom<&BenchmarkStep> for BenchmarkDatum {
         Ok(BenchmarkDatum {
             name: step.name.clone(),
             points: vec![(step.start, end)],
+            cycles: step.cycles.clone().unwrap_or_default(),
         })
     }
 }
 
 #[derive(Debug)]
-struct GraphableBenchmark {
-    name: String,
+pub struct GraphableBenchmark {
+    pub name: String,
     language: Option<String>,
     error: Option<String>,
-    data: Vec<BenchmarkDatum>,
+    pub data: Vec<BenchmarkDatum>,
+    pub totals: Vec<f64>,
+    excluded: usize,
 }
 
 impl GraphableBenchmark {
@@ -63,17 +115,39 @@ impl GraphableBenchmark {
         for datum in self.data.iter_mut() {
             if let Some(other_datum) = hm.remove(&datum.name) {
                 datum.points.extend(other_datum.points);
+                datum.cycles.extend(other_datum.cycles);
             }
         }
         let leftover: Vec<BenchmarkDatum> = hm.into_iter().map(|(_k, v)| v).collect();
         self.data.extend(leftover);
     }
 
+    fn total_summary(&self) -> Option<Summary> {
+        Summary::from_samples(&self.totals)
+    }
+
     fn max_bench_len(&self) -> f64 {
         self.data.iter().fold(0.0, |max_len, datum| {
             f64::max(max_len, datum.average_span().1)
         })
     }
+
+    /// The sum of each step's average duration, which is longer than the run
+    /// when steps overlap.
+    fn stacked_len(&self) -> f64 {
+        self.data.iter().map(BenchmarkDatum::average_duration).sum()
+    }
+}
+
+/// The name of every step of the benchmarks, in the order they first appear.
+fn step_names(benchmarks: &[GraphableBenchmark]) -> Vec<&str> {
+    let mut names: Vec<&str> = vec![];
+    for datum in benchmarks.iter().flat_map(|bench| bench.data.iter()) {
+        if !names.contains(&datum.name.as_str()) {
+            names.push(&datum.name);
+        }
+    }
+    names
 }
 
 fn graph_entries(benchmarks: &Vec<GraphableBenchmark>) -> (Vec<ViewBuilder<Dom>>, f32) {
@@ -110,7 +184,18 @@ fn graph_entries(benchmarks: &Vec<GraphableBenchmark>) -> (Vec<ViewBuilder<Dom>>
         };
 
         let total = gbench.max_bench_len();
-        let total_text_string = format!("{}ms", total.round() as u32);
+        let total_summary = gbench.total_summary();
+        // Label the mean of the totals the interval is around, the bar is
+        // the span of the average steps
+        let total_text_string = match total_summary {
+            Some(summary) if summary.n > 1 => format!(
+                "{}ms ±{}",
+                summary.mean.round() as u32,
+                summary.ci95_half_width().round() as u32
+            ),
+            Some(summary) => format!("{}ms", summary.mean.round() as u32),
+            None => format!("{}ms", total.round() as u32),
+        };
         let total_text = builder! {
             <text xmlns=SVGNS class="framework-text"
              x=format!("{}", graph_start + font_size)
@@ -153,7 +238,18 @@ fn graph_entries(benchmarks: &Vec<GraphableBenchmark>) -> (Vec<ViewBuilder<Dom>>
                  opacity="0.4">
 
                     <title xmlns=SVGNS>
-                        {format!("total bench time - {}ms", total.round() as u32)}
+                        {format!(
+                            "total bench time - {}ms{}{}",
+                            total.round() as u32,
+                            total_summary
+                                .map(|summary| format!("\n{}", summary))
+                                .unwrap_or_default(),
+                            if gbench.excluded > 0 {
+                                format!("\n{} excluded runs", gbench.excluded)
+                            } else {
+                                String::new()
+                            }
+                        )}
                     </title>
 
                 </rect>
@@ -167,20 +263,9 @@ fn graph_entries(benchmarks: &Vec<GraphableBenchmark>) -> (Vec<ViewBuilder<Dom>>
                     datum.name
                 );
 
-                let (min, max) = datum.points.iter().fold(
-                    (f64::INFINITY, f64::NEG_INFINITY),
-                    |(n, x), (start, end)| (f64::min(n, *start), f64::max(x, *end)),
-                );
                 let (start, end) = datum.average_span();
                 let (x, width) = to_x_and_width(start as f32, end as f32);
-                log::trace!(
-                    "{:#?} min:{} max:{} x:{} width:{}",
-                    datum,
-                    min,
-                    max,
-                    x,
-                    width
-                );
+                log::trace!("{:#?} x:{} width:{}", datum, x, width);
                 let event_bar = builder! {
                     <rect xmlns=SVGNS
                      x=format!("{}", x)
@@ -195,11 +280,18 @@ fn graph_entries(benchmarks: &Vec<GraphableBenchmark>) -> (Vec<ViewBuilder<Dom>>
 
                         <title xmlns=SVGNS>
                             {format!(
-                                "{} took {}ms ({} to {})",
+                                "{} took {}ms ({} to {}){}{}",
                                 datum.name,
                                 (end - start).round() as u32,
                                 start.round() as u32,
-                                end.round() as u32
+                                end.round() as u32,
+                                datum
+                                    .summary()
+                                    .map(|summary| format!("\n{}", summary))
+                                    .unwrap_or_default(),
+                                Percentiles::from_samples(&datum.cycles)
+                                    .map(|p| format!("\nper operation: {}", p))
+                                    .unwrap_or_default()
                             )}
                         </title>
 
@@ -217,11 +309,280 @@ fn graph_entries(benchmarks: &Vec<GraphableBenchmark>) -> (Vec<ViewBuilder<Dom>>
     (tags, next_y)
 }
 
+/// Where the legend entry of each name goes, starting on the line at `y` and
+/// wrapping onto new lines as needed.
+fn legend_positions(names: &[&str], font_size: f32, y: f32) -> Vec<(f32, f32)> {
+    let mut positions = vec![];
+    let mut x = 0.0;
+    let mut y = y;
+    for name in names.iter() {
+        let width = font_size * 2.0 + name.len() as f32 * font_size * 0.6;
+        if x > 0.0 && x + width > 960.0 {
+            x = 0.0;
+            y += font_size + 6.0;
+        }
+        positions.push((x, y));
+        x += width;
+    }
+    positions
+}
+
+/// A swatch and name for each step, and the y of the last line.
+fn legend(names: &[&str], font_size: f32, y: f32) -> (Vec<ViewBuilder<Dom>>, f32) {
+    let positions = legend_positions(names, font_size, y);
+    let last_y = positions.last().map(|(_, y)| *y).unwrap_or(y);
+    let mut tags = vec![];
+    for (index, (name, (x, y))) in names.iter().zip(positions).enumerate() {
+        tags.push(builder! {
+            <rect xmlns=SVGNS
+             x=format!("{}", x)
+             y=format!("{}", y - font_size + 2.0)
+             width="10"
+             height="10"
+             fill=palette_color(index)>
+            </rect>
+        });
+        tags.push(builder! {
+            <text xmlns=SVGNS font_family="monospace" font_size="12"
+             x=format!("{}", x + 14.0)
+             y=format!("{}", y)>
+                {name.to_string()}
+            </text>
+        });
+    }
+    (tags, last_y)
+}
+
+fn stacked_entries(benchmarks: &Vec<GraphableBenchmark>) -> (Vec<ViewBuilder<Dom>>, f32) {
+    let font_size = 12.0;
+    let names = step_names(benchmarks);
+    let (mut tags, legend_y) = legend(&names, font_size, font_size);
+    let max_total = benchmarks
+        .iter()
+        .fold(1.0, |max, bench| f64::max(max, bench.stacked_len()));
+
+    let padding = 8.0;
+    let lane_height = font_size + padding;
+    let bar_height = lane_height - 2.0;
+    let max_bar_width = 960.0;
+    let mut next_y = legend_y + padding;
+
+    for gbench in benchmarks.iter() {
+        let text_y = next_y + (lane_height / 2.0) + (font_size / 2.0);
+        let total = gbench.stacked_len();
+        let status = match gbench.error.as_ref() {
+            Some(fail_msg) => format!("Failed: {}", fail_msg),
+            None => format!("{}ms", total.round() as u32),
+        };
+        tags.push(builder! {
+            <text xmlns=SVGNS font_family="monospace" font_size="12" x="0" y=format!("{}", text_y)>
+                {format!("{} {}", gbench.name, status)}
+            </text>
+        });
+        next_y += lane_height;
+        if gbench.error.is_some() {
+            continue;
+        }
+
+        let mut x = 0.0;
+        for datum in gbench.data.iter() {
+            let duration = datum.average_duration();
+            let width = (duration / max_total) as f32 * max_bar_width;
+            let color = names
+                .iter()
+                .position(|name| *name == datum.name)
+                .map(palette_color)
+                .unwrap_or("grey");
+            tags.push(builder! {
+                <rect xmlns=SVGNS
+                 x=format!("{}", x)
+                 y=format!("{}", next_y + 1.0)
+                 width=format!("{}", f32::max(width, 1.0))
+                 height=format!("{}", bar_height)
+                 fill=color
+                 stroke="white"
+                 style="cursor: pointer;">
+
+                    <title xmlns=SVGNS>
+                        {format!(
+                            "{} took {}ms{}",
+                            datum.name,
+                            duration.round() as u32,
+                            datum
+                                .summary()
+                                .map(|summary| format!("\n{}", summary))
+                                .unwrap_or_default()
+                        )}
+                    </title>
+
+                </rect>
+            });
+            x += width;
+        }
+        next_y += bar_height;
+    }
+
+    (tags, next_y)
+}
+
+/// A box and whiskers of one row of samples.
+fn box_entry(
+    plot: &BoxPlot,
+    to_x: impl Fn(f64) -> f32,
+    y: f32,
+    height: f32,
+    color: &'static str,
+    title: String,
+) -> Vec<ViewBuilder<Dom>> {
+    let (mid, bottom) = (y + height / 2.0, y + height);
+    let (cap_top, cap_bottom) = (y + 2.0, bottom - 2.0);
+    let (low, high) = (to_x(plot.low), to_x(plot.high));
+    let line = |(x1, y1): (f32, f32), (x2, y2): (f32, f32), stroke_width: &'static str| {
+        builder! {
+            <line xmlns=SVGNS
+             x1=format!("{}", x1)
+             x2=format!("{}", x2)
+             y1=format!("{}", y1)
+             y2=format!("{}", y2)
+             stroke="black"
+             stroke_width=stroke_width>
+            </line>
+        }
+    };
+    let mut tags = vec![
+        line((low, mid), (to_x(plot.q1), mid), "1"),
+        line((to_x(plot.q3), mid), (high, mid), "1"),
+        line((low, cap_top), (low, cap_bottom), "1"),
+        line((high, cap_top), (high, cap_bottom), "1"),
+        builder! {
+            <rect xmlns=SVGNS
+             x=format!("{}", to_x(plot.q1))
+             y=format!("{}", y)
+             width=format!("{}", f32::max(to_x(plot.q3) - to_x(plot.q1), 1.0))
+             height=format!("{}", height)
+             fill=color
+             stroke="black"
+             opacity="0.6"
+             style="cursor: pointer;">
+                <title xmlns=SVGNS>{title}</title>
+            </rect>
+        },
+        line((to_x(plot.median), y), (to_x(plot.median), bottom), "2"),
+    ];
+    for outlier in plot.outliers.iter() {
+        tags.push(builder! {
+            <circle xmlns=SVGNS
+             cx=format!("{}", to_x(*outlier))
+             cy=format!("{}", mid)
+             r="2"
+             fill="none"
+             stroke="black">
+            </circle>
+        });
+    }
+    tags
+}
+
+fn box_entries(benchmarks: &Vec<GraphableBenchmark>) -> (Vec<ViewBuilder<Dom>>, f32) {
+    let font_size = 12.0;
+    let padding = 8.0;
+    let lane_height = font_size + padding;
+    let box_height = lane_height - 6.0;
+    let mut tags = vec![];
+    let mut next_y = font_size;
+
+    // A row for the total and for each step of every framework that didn't fail
+    let rows = benchmarks
+        .iter()
+        .map(|gbench| {
+            let mut rows = vec![];
+            if gbench.error.is_none() {
+                rows.extend(BoxPlot::from_samples(&gbench.totals).map(|plot| ("total", plot)));
+                for datum in gbench.data.iter() {
+                    rows.extend(
+                        BoxPlot::from_samples(&datum.durations())
+                            .map(|plot| (datum.name.as_str(), plot)),
+                    );
+                }
+            }
+            rows
+        })
+        .collect::<Vec<_>>();
+    let all_rows = || rows.iter().flatten();
+    let max = all_rows()
+        .flat_map(|(_, plot)| plot.outliers.iter().chain(std::iter::once(&plot.high)))
+        .fold(1.0, |max, ms| f64::max(max, *ms));
+    let label_width = all_rows().fold(0.0, |max, (name, _)| {
+        f32::max(max, name.len() as f32 * font_size * 0.6)
+    });
+    let graph_start = label_width + padding;
+    let graph_width = 960.0 - graph_start - padding;
+    let to_x = |ms: f64| graph_start + (ms / max) as f32 * graph_width;
+
+    if benchmarks.iter().all(|gbench| gbench.totals.len() < 2) {
+        tags.push(builder! {
+            <text xmlns=SVGNS font_family="monospace" font_size="12" x="0" y=format!("{}", next_y)>
+                "Each framework ran once, average over more runs to see the spread of their times."
+            </text>
+        });
+        next_y += lane_height;
+    }
+    tags.push(builder! {
+        <text xmlns=SVGNS font_family="monospace" font_size="12" text_anchor="end"
+         x=format!("{}", to_x(max))
+         y=format!("{}", next_y)>
+            {format!("{}ms", max.round() as u32)}
+        </text>
+    });
+
+    for (gbench, rows) in benchmarks.iter().zip(rows.iter()) {
+        let text_y = next_y + lane_height;
+        let heading = match gbench.error.as_ref() {
+            Some(fail_msg) => format!("{} Failed: {}", gbench.name, fail_msg),
+            None => format!("{} ({} runs)", gbench.name, gbench.totals.len()),
+        };
+        tags.push(builder! {
+            <text xmlns=SVGNS font_family="monospace" font_size="12" font_weight="bold"
+             x="0"
+             y=format!("{}", text_y)>
+                {heading}
+            </text>
+        });
+        next_y += lane_height;
+
+        let color = lang_color(gbench.language.as_ref());
+        for (name, plot) in rows.iter() {
+            tags.push(builder! {
+                <text xmlns=SVGNS font_family="monospace" font_size="12"
+                 x="0"
+                 y=format!("{}", next_y + lane_height / 2.0 + font_size / 2.0)>
+                    {name.to_string()}
+                </text>
+            });
+            let title = format!("{} of {}\n{}", name, gbench.name, plot);
+            tags.extend(box_entry(
+                plot,
+                to_x,
+                next_y + (lane_height - box_height) / 2.0,
+                box_height,
+                color,
+                title,
+            ));
+            next_y += lane_height;
+        }
+    }
+
+    (tags, next_y)
+}
+
 fn process_benchmark_data(steps: &Vec<BenchmarkStep>) -> Vec<BenchmarkDatum> {
     steps.iter().flat_map(BenchmarkDatum::try_from).collect()
 }
 
-fn process_benchmarks(benchmarks: &Vec<Benchmark>) -> Vec<GraphableBenchmark> {
+/// Merge the steps of each framework's runs, leaving out excluded and failed
+/// runs. A framework's error is that of its last failed run that wasn't
+/// excluded, so a failed warm-up doesn't mark it failed.
+pub fn process_benchmarks(benchmarks: &Vec<Benchmark>) -> Vec<GraphableBenchmark> {
     let mut bench_map: HashMap<String, GraphableBenchmark> = HashMap::new();
     for benchmark in benchmarks.iter() {
         log::trace!("{:#?}", benchmark);
@@ -230,9 +591,21 @@ fn process_benchmarks(benchmarks: &Vec<Benchmark>) -> Vec<GraphableBenchmark> {
             .or_insert(GraphableBenchmark {
                 name: benchmark.name.clone(),
                 language: benchmark.language.clone(),
-                error: benchmark.failed_message.clone(),
+                error: None,
                 data: vec![],
+                totals: vec![],
+                excluded: 0,
             });
+        if benchmark.excluded.is_some() {
+            entry.excluded += 1;
+            continue;
+        }
+        // A failed run's steps stop short, so they aren't mixed in either
+        if let Some(msg) = benchmark.failed_message.as_ref() {
+            entry.error = Some(msg.clone());
+            continue;
+        }
+        entry.totals.extend(benchmark.total());
         let data = process_benchmark_data(&benchmark.steps);
         log::trace!("{:#?}", data);
         entry.merge_data(data);
@@ -242,11 +615,19 @@ fn process_benchmarks(benchmarks: &Vec<Benchmark>) -> Vec<GraphableBenchmark> {
     bench_map.into_iter().map(|(_, v)| v).collect()
 }
 
-pub fn graph_benchmarks(benchmarks: &Vec<Benchmark>) -> ViewBuilder<Dom> {
+pub fn graph_benchmarks(benchmarks: &Vec<Benchmark>, mode: GraphMode) -> ViewBuilder<Dom> {
     let mut benchmarks = process_benchmarks(benchmarks);
+    let len = |bench: &GraphableBenchmark| match mode {
+        GraphMode::Timeline => bench.max_bench_len(),
+        GraphMode::Stacked => bench.stacked_len(),
+        GraphMode::Box => bench
+            .total_summary()
+            .map(|summary| summary.median)
+            .unwrap_or_default(),
+    };
     benchmarks.sort_by(|bencha, benchb| {
-        let a = bencha.max_bench_len().round() as u32;
-        let b = benchb.max_bench_len().round() as u32;
+        let a = len(bencha).round() as u32;
+        let b = len(benchb).round() as u32;
         let time_ord = a.cmp(&b);
         if bencha.error.is_some() {
             std::cmp::Ordering::Greater
@@ -257,18 +638,103 @@ pub fn graph_benchmarks(benchmarks: &Vec<Benchmark>) -> ViewBuilder<Dom> {
         }
     });
 
-    let (entries, height) = graph_entries(&benchmarks);
+    let (entries, height) = match mode {
+        GraphMode::Timeline => graph_entries(&benchmarks),
+        GraphMode::Stacked => stacked_entries(&benchmarks),
+        GraphMode::Box => box_entries(&benchmarks),
+    };
     let height = height + 10.0;
+    // Box plots take a few rows per framework, more than fit the container,
+    // so they keep their aspect and scroll instead of shrinking to fit
+    let scrolls = mode == GraphMode::Box;
     let graph = builder!{
         <svg xmlns=SVGNS
          width="960"
          height=format!("{}", height)
          viewBox=format!("0 0 960 {}", height)
-         class="embed-responsive-item">
+         class=if scrolls { "w-100 h-auto" } else { "embed-responsive-item" }>
 
             {entries}
 
         </svg>
     };
-    graph
+    if scrolls {
+        builder! {
+            <div class="embed-responsive-item overflow-auto">{graph}</div>
+        }
+    } else {
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench_runner::Exclusion;
+
+    #[test]
+    fn stacks_the_average_of_each_step() {
+        let mut overlapping = Benchmark::sequential("a", &[("create", 10.0), ("delete", 4.0)]);
+        overlapping
+            .steps
+            .push(BenchmarkStep::finished("render", 5.0, 11.0));
+        let benchmarks = vec![
+            overlapping,
+            Benchmark::sequential("a", &[("create", 20.0), ("delete", 6.0)]),
+        ];
+        let graphable = process_benchmarks(&benchmarks);
+        assert_eq!(graphable.len(), 1);
+        // The steps overlap, so the stack is longer than the run
+        assert_eq!(graphable[0].stacked_len(), 15.0 + 5.0 + 6.0);
+        assert!(graphable[0].stacked_len() > graphable[0].max_bench_len());
+    }
+
+    #[test]
+    fn fails_frameworks_by_their_measured_runs() {
+        let failed = |excluded: Option<Exclusion>| Benchmark {
+            failed_message: Some("timed out".to_string()),
+            excluded,
+            ..Benchmark::sequential("a", &[("create", 1.0)])
+        };
+        let good = Benchmark::sequential("a", &[("create", 10.0)]);
+
+        let graphable = process_benchmarks(&vec![failed(Some(Exclusion::WarmUp)), good.clone()]);
+        assert_eq!(graphable[0].error, None);
+        assert_eq!(graphable[0].totals, vec![10.0]);
+
+        let graphable = process_benchmarks(&vec![good, failed(None)]);
+        assert_eq!(graphable[0].error.as_deref(), Some("timed out"));
+        assert_eq!(graphable[0].totals, vec![10.0]);
+    }
+
+    #[test]
+    fn names_each_step_once_in_order() {
+        let benchmarks = vec![
+            Benchmark::sequential("a", &[("create", 1.0), ("delete", 1.0)]),
+            Benchmark::sequential("b", &[("create", 1.0), ("toggle", 1.0), ("delete", 1.0)]),
+        ];
+        let mut graphable = process_benchmarks(&benchmarks);
+        graphable.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(step_names(&graphable), vec!["create", "delete", "toggle"]);
+    }
+
+    #[test]
+    fn wraps_the_legend_at_the_graph_width() {
+        let positions = legend_positions(&["create", "delete"], 12.0, 20.0);
+        assert_eq!(positions, vec![(0.0, 20.0), (67.2, 20.0)]);
+
+        let long = "x".repeat(150);
+        let names = vec!["create", long.as_str(), long.as_str()];
+        let positions = legend_positions(&names, 12.0, 20.0);
+        assert_eq!(positions[0], (0.0, 20.0));
+        // Too wide to follow "create", so it starts the next line, and the
+        // next one can't follow it either
+        assert_eq!(positions[1], (0.0, 38.0));
+        assert_eq!(positions[2], (0.0, 56.0));
+        // An entry wider than the graph still gets a line of its own
+        assert_eq!(
+            legend_positions(&names[1..2], 12.0, 20.0),
+            vec![(0.0, 20.0)]
+        );
+    }
 }
@@ -0,0 +1,117 @@
+//! Lists past runs so they can be reopened, renamed and deleted.
+use mogwai::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlInputElement;
+
+use super::{
+    bench_runner::{Scenarios, WaitStrategy},
+    store::RunInfo,
+    In,
+};
+
+fn config_text(run: &RunInfo) -> String {
+    run.config
+        .map(|config| {
+            let mut text = format!(
+                "{} todos, wait by {}, end at {}",
+                config.todos,
+                match config.wait {
+                    WaitStrategy::Poll => "polling",
+                    WaitStrategy::Mutation => "mutations",
+                },
+                if config.settle { "paint" } else { "dom ready" }
+            );
+            if config.scenarios != Scenarios::default() {
+                let enabled = config.scenarios.enabled();
+                if enabled.is_empty() {
+                    text += ", scenarios: none";
+                } else {
+                    text += &format!(", scenarios: {}", enabled.join(", "));
+                }
+            }
+            text
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn run_row(run: &RunInfo, is_open: bool, tx: &broadcast::Sender<In>) -> ViewBuilder<Dom> {
+    let rename_id = run.id.clone();
+    let open_id = run.id.clone();
+    let baseline_id = run.id.clone();
+    let delete_id = run.id.clone();
+    let delete_name = run.name.clone();
+    builder! {
+        <tr class=if is_open { "table-active" } else { "" }>
+            <td>
+                <input
+                 type="text"
+                 class="form-control form-control-sm"
+                 value=&run.name
+                 on:change = tx.sink().contra_filter_map(move |event: Event| {
+                     let input = event.target()?.dyn_into::<HtmlInputElement>().ok()?;
+                     Some(In::RenameRun(rename_id.clone(), input.value()))
+                 })
+                />
+            </td>
+            <td>{run.date_string()}</td>
+            <td>{config_text(run)}</td>
+            <td title=&run.user_agent>{&run.crate_version}</td>
+            <td>
+                <button
+                 class="btn btn-sm btn-outline-primary mr-1"
+                 boolean:disabled=is_open
+                 on:click = tx.sink().contra_map(move |_| In::OpenRun(open_id.clone()))>
+                    "Open"
+                </button>
+                <button
+                 class="btn btn-sm btn-outline-secondary mr-1"
+                 title="compare the shown run with this one"
+                 on:click = tx.sink().contra_map(move |_| In::SetBaseline(baseline_id.clone()))>
+                    "Baseline"
+                </button>
+                <button
+                 class="btn btn-sm btn-outline-danger"
+                 on:click = tx.sink().contra_filter_map(move |_| {
+                     let confirmed = mogwai::utils::window()
+                         .confirm_with_message(&format!("Delete '{}'?", delete_name))
+                         .unwrap_or(false);
+                     if confirmed {
+                         Some(In::DeleteRun(delete_id.clone()))
+                     } else {
+                         None
+                     }
+                 })>
+                    "Delete"
+                </button>
+            </td>
+        </tr>
+    }
+}
+
+/// A table of the given runs, highlighting the open one.
+pub fn history_view(
+    runs: &[RunInfo],
+    open_id: Option<&str>,
+    tx: &broadcast::Sender<In>,
+) -> ViewBuilder<Dom> {
+    let rows = runs
+        .iter()
+        .map(|run| run_row(run, Some(run.id.as_str()) == open_id, tx))
+        .collect::<Vec<_>>();
+    builder! {
+        <table class="table table-sm">
+            <thead>
+                <tr>
+                    <th scope="col">"Run"</th>
+                    <th scope="col">"Date"</th>
+                    <th scope="col">"Config"</th>
+                    <th scope="col">"Version"</th>
+                    <th scope="col"></th>
+                </tr>
+            </thead>
+            <tbody>
+                {rows}
+            </tbody>
+        </table>
+    }
+}
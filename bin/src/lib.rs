@@ -2,18 +2,44 @@ use log::{trace, Level};
 use mogwai::{lock::RwLock, prelude::*};
 use rand::{seq::SliceRandom, thread_rng};
 use std::{collections::HashMap, panic, sync::Arc};
+use todo_mvc_bench_core::{
+    report::{self, Report},
+    schema,
+    stats::{self, OutlierFences, Summary},
+};
 use todo_mvc_bench_lib::{wait_for, wait_while};
 use wasm_bindgen::prelude::*;
-use web_sys::{HtmlInputElement, KeyboardEvent, SvgsvgElement};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{File, HtmlInputElement, HtmlSelectElement, KeyboardEvent, SvgsvgElement};
+
+mod backend;
 
 mod bench_runner;
-use bench_runner::{BenchRunnerFacade, Benchmark};
+use bench_runner::{
+    BenchConfig, BenchRunnerFacade, Benchmark, Exclusion, ResourceSizes, WaitStrategy,
+};
+
+mod compare;
+mod export;
 
 mod framework_card;
-use framework_card::{all_cards, FrameworkCard, FrameworkFacade, FrameworkState};
+use framework_card::{FrameworkCard, FrameworkFacade, FrameworkState};
 
 mod graph;
+use graph::GraphMode;
+mod history;
+mod manifest;
+mod query;
+use query::Preset;
+mod regression;
+use regression::Baseline;
+mod score;
+mod share;
+
 mod store;
+use store::{RunInfo, Store};
+mod trend;
+use trend::FrameworkTimes;
 
 //#[cfg(test)]
 //mod bench_tests {
@@ -84,8 +110,29 @@ pub enum In {
         hit_enter: bool,
     },
     SoloFramework(String),
+    WarmUpsChange(u32),
+    OutlierFencesChange(OutlierFences),
+    TodoCountChange(usize),
+    WaitStrategyChange(WaitStrategy),
+    SettleChange(bool),
     ClickedRun,
     ToggleAll,
+    ExportJson,
+    ExportCsv,
+    Share,
+    MergeImportChange(bool),
+    ImportFile(File),
+    OpenRun(String),
+    RenameRun(String, String),
+    DeleteRun(String),
+    SetBaseline(String),
+    ImportBaseline(File),
+    ClearBaseline,
+    RegressionThresholdChange(f64),
+    ExportReport,
+    // The step to chart the trend of, or the totals.
+    TrendStepChange(Option<String>),
+    GraphModeChange(GraphMode),
 }
 
 impl In {
@@ -109,15 +156,179 @@ impl In {
             hit_enter,
         }
     }
+
+    fn from_warm_ups_change_event(event: web_sys::Event) -> Option<In> {
+        let select = event.target()?.dyn_into::<HtmlSelectElement>().ok()?;
+        let warm_ups = select.value().parse::<u32>().ok()?;
+        Some(In::WarmUpsChange(warm_ups))
+    }
+
+    fn from_outlier_fences_change_event(event: web_sys::Event) -> Option<In> {
+        let select = event.target()?.dyn_into::<HtmlSelectElement>().ok()?;
+        match select.value().as_str() {
+            "off" => Some(In::OutlierFencesChange(OutlierFences::Off)),
+            "inner" => Some(In::OutlierFencesChange(OutlierFences::Inner)),
+            "outer" => Some(In::OutlierFencesChange(OutlierFences::Outer)),
+            _ => None,
+        }
+    }
+
+    fn from_merge_import_change_event(event: web_sys::Event) -> Option<In> {
+        let input = event.target()?.dyn_into::<HtmlInputElement>().ok()?;
+        Some(In::MergeImportChange(input.checked()))
+    }
+
+    fn picked_file(event: web_sys::Event) -> Option<File> {
+        let input = event.target()?.dyn_into::<HtmlInputElement>().ok()?;
+        let file = input.files()?.get(0)?;
+        // Let the same file be picked again
+        input.set_value("");
+        Some(file)
+    }
+
+    fn from_import_file_event(event: web_sys::Event) -> Option<In> {
+        In::picked_file(event).map(In::ImportFile)
+    }
+
+    fn from_import_baseline_event(event: web_sys::Event) -> Option<In> {
+        In::picked_file(event).map(In::ImportBaseline)
+    }
+
+    fn from_regression_threshold_event(event: web_sys::Event) -> Option<In> {
+        let input = event.target()?.dyn_into::<HtmlInputElement>().ok()?;
+        let percent = input.value().trim().parse::<f64>().ok()?;
+        if percent >= 0.0 {
+            Some(In::RegressionThresholdChange(percent / 100.0))
+        } else {
+            None
+        }
+    }
+
+    fn from_todo_count_change_event(event: web_sys::Event) -> Option<In> {
+        let select = event.target()?.dyn_into::<HtmlSelectElement>().ok()?;
+        let todos = select.value().parse::<usize>().ok()?;
+        Some(In::TodoCountChange(todos))
+    }
+
+    fn from_wait_strategy_change_event(event: web_sys::Event) -> Option<In> {
+        let select = event.target()?.dyn_into::<HtmlSelectElement>().ok()?;
+        match select.value().as_str() {
+            "poll" => Some(In::WaitStrategyChange(WaitStrategy::Poll)),
+            "mutation" => Some(In::WaitStrategyChange(WaitStrategy::Mutation)),
+            _ => None,
+        }
+    }
+
+    fn from_settle_change_event(event: web_sys::Event) -> Option<In> {
+        let select = event.target()?.dyn_into::<HtmlSelectElement>().ok()?;
+        Some(In::SettleChange(select.value() == "paint"))
+    }
+
+    fn from_graph_mode_change_event(event: web_sys::Event) -> Option<In> {
+        let select = event.target()?.dyn_into::<HtmlSelectElement>().ok()?;
+        match select.value().as_str() {
+            "timeline" => Some(In::GraphModeChange(GraphMode::Timeline)),
+            "stacked" => Some(In::GraphModeChange(GraphMode::Stacked)),
+            "box" => Some(In::GraphModeChange(GraphMode::Box)),
+            _ => None,
+        }
+    }
+
+    fn from_trend_step_change_event(event: web_sys::Event) -> Option<In> {
+        let select = event.target()?.dyn_into::<HtmlSelectElement>().ok()?;
+        let step = select.value();
+        Some(In::TrendStepChange(if step.is_empty() {
+            None
+        } else {
+            Some(step)
+        }))
+    }
 }
 
 pub struct App {
     cards: HashMap<String, FrameworkFacade>,
-    //benchmarks: Vec<Benchmark>,
+    // The benchmarks being shown.
+    benchmarks: Vec<Benchmark>,
+    // Whether imported benchmarks are shown alongside the shown ones, instead
+    // of replacing them.
+    merge_import: bool,
     avg_times: u32,
+    // Number of runs of each framework made, and excluded, before the
+    // averaged runs.
+    warm_ups: u32,
+    outlier_fences: OutlierFences,
+    bench_config: BenchConfig,
+    // The id of the stored run being shown.
+    open_run: Option<String>,
+    // Whether to start a run as soon as the page is ready.
+    autorun: bool,
+    // The run the shown one is checked for regressions against.
+    baseline: Option<Baseline>,
+    // How much slower than the baseline a step has to be to regress, as a
+    // fraction.
+    regression_threshold: f64,
+    // The step charted across stored runs, or `None` for the totals.
+    trend_step: Option<String>,
+    graph_mode: GraphMode,
 }
 
 impl App {
+    /// The warm-up counts offered in the navbar.
+    const WARM_UPS: [u32; 4] = [0, 1, 2, 5];
+
+    /// The name of an exported file of the open run.
+    fn export_filename(&self, extension: &str) -> String {
+        match self.open_run.as_ref() {
+            Some(id) => format!("todo-mvc-bench-{}.{}", id, extension),
+            None => format!("todo-mvc-bench.{}", extension),
+        }
+    }
+
+    /// Read exported benchmarks from a file. When merging, each imported
+    /// framework is labeled with the file it came from, so it can be told
+    /// apart from the shown run of that framework.
+    async fn import(&self, file: &File) -> Result<Vec<Benchmark>, String> {
+        let mut imported = read_exported(file).await?;
+        if !self.merge_import {
+            return Ok(imported);
+        }
+        let label = file.name().trim_end_matches(".json").to_string();
+        for benchmark in imported.iter_mut() {
+            benchmark.name = format!("{} ({})", benchmark.name, label);
+        }
+        let mut merged = self.benchmarks.clone();
+        merged.extend(imported);
+        Ok(merged)
+    }
+
+    /// Score the given benchmarks and summarize their total times, showing
+    /// both on each card along with the sizes measured in them.
+    async fn show_results(&self, benchmarks: &[Benchmark]) {
+        let scores = score::scores(benchmarks);
+        let mut totals: HashMap<&str, Vec<f64>> = HashMap::new();
+        let mut sizes: HashMap<&str, ResourceSizes> = HashMap::new();
+        for benchmark in benchmarks.iter() {
+            // Warm-ups download the same files, so any run's sizes will do
+            if let Some(benchmark_sizes) = benchmark.sizes.as_ref() {
+                sizes.insert(benchmark.name.as_str(), benchmark_sizes.clone());
+            }
+            if benchmark.is_sample() {
+                totals
+                    .entry(benchmark.name.as_str())
+                    .or_default()
+                    .extend(benchmark.total());
+            }
+        }
+        for (name, facade) in self.cards.iter() {
+            facade.set_score(scores.get(name).cloned()).await;
+            let total = totals
+                .get(name.as_str())
+                .and_then(|samples| Summary::from_samples(samples));
+            facade.set_total(total).await;
+            facade.set_sizes(sizes.get(name.as_str()).cloned()).await;
+        }
+    }
+
     //pub fn new() -> Self {
     //    let cards = all_cards()
     //        .into_iter()
@@ -143,6 +354,114 @@ pub enum Out {
     RunDisabled(bool),
 }
 
+/// Read benchmarks exported as JSON from a file.
+async fn read_exported(file: &File) -> Result<Vec<Benchmark>, String> {
+    let text = JsFuture::from(file.text())
+        .await
+        .ok()
+        .and_then(|text| text.as_string())
+        .ok_or_else(|| format!("could not read {}", file.name()))?;
+    export::from_json(&text).map_err(|e| format!("could not import {}: {}", file.name(), e))
+}
+
+/// Replace the contents of the graph container with a graph of the given
+/// benchmarks.
+fn show_graph(container_dom: &Dom, benchmarks: &Vec<Benchmark>, mode: GraphMode) {
+    let graph = Component::from(graph::graph_benchmarks(benchmarks, mode))
+        .build()
+        .unwrap_or_else(|e| panic!("couldn't create the graph: {}", e))
+        .into_inner();
+    container_dom
+        .patch_children(ListPatch::splice(.., std::iter::once(graph)))
+        .unwrap();
+}
+
+/// Replace the list of stored runs.
+async fn show_history(
+    store: &Store,
+    history_dom: &Dom,
+    open_run: Option<&str>,
+    tx: &broadcast::Sender<In>,
+) {
+    let runs = store.list_runs().await.unwrap_or_else(|err| {
+        log::error!("could not list stored runs: {:?}", err);
+        vec![]
+    });
+    let history = Component::from(history::history_view(&runs, open_run, tx))
+        .build()
+        .unwrap()
+        .into_inner();
+    history_dom
+        .patch_children(ListPatch::splice(.., std::iter::once(history)))
+        .unwrap();
+}
+
+/// Read the times of every stored run, oldest first, for the trend chart.
+/// Runs that can't be read are left out.
+async fn read_trend_runs(store: &Store) -> Vec<(RunInfo, Vec<FrameworkTimes>)> {
+    let mut infos = store.list_runs().await.unwrap_or_else(|err| {
+        log::error!("could not list stored runs: {:?}", err);
+        vec![]
+    });
+    infos.sort_by(|a, b| {
+        a.timestamp
+            .partial_cmp(&b.timestamp)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let mut runs = vec![];
+    for info in infos.into_iter() {
+        match store.read_run_times(&info.id).await {
+            Ok(times) => runs.push((info, times)),
+            Err(err) => log::error!("could not read run {} for the trend: {:?}", info.id, err),
+        }
+    }
+    runs
+}
+
+/// Replace the chart of times across stored runs with one of the app's
+/// trend step.
+fn show_trend(
+    trend_dom: &Dom,
+    runs: &[(RunInfo, Vec<FrameworkTimes>)],
+    app: &App,
+    tx: &broadcast::Sender<In>,
+) {
+    let trend = Component::from(trend::trend_view(runs, app.trend_step.as_deref(), tx))
+        .build()
+        .unwrap()
+        .into_inner();
+    trend_dom
+        .patch_children(ListPatch::splice(.., std::iter::once(trend)))
+        .unwrap();
+}
+
+/// Replace the comparison of two frameworks with one of the given benchmarks,
+/// or remove it if fewer than two frameworks have samples.
+fn show_comparison(compare_dom: &Dom, benchmarks: &Vec<Benchmark>) {
+    let comparison =
+        compare::comparison(benchmarks).map(|component| component.build().unwrap().into_inner());
+    compare_dom
+        .patch_children(ListPatch::splice(.., comparison.into_iter()))
+        .unwrap();
+}
+
+/// Replace the regression check with one of the shown benchmarks against the
+/// baseline.
+fn show_regressions(regression_dom: &Dom, app: &App, tx: &broadcast::Sender<In>) {
+    let regressions = Component::from(regression::regression_view(
+        app.baseline.as_ref(),
+        &app.benchmarks,
+        app.regression_threshold,
+        tx,
+    ))
+    .build()
+    .unwrap()
+    .into_inner();
+    regression_dom
+        .patch_children(ListPatch::splice(.., std::iter::once(regressions)))
+        .unwrap();
+}
+
 async fn app_logic(
     mut app: App,
     tx_logic: broadcast::Sender<In>,
@@ -150,20 +469,48 @@ async fn app_logic(
     mut rx_cancel: broadcast::Receiver<()>,
     tx_view: broadcast::Sender<Out>,
     tx_container: mpmc::Receiver<Dom>,
+    tx_compare: mpmc::Receiver<Dom>,
+    tx_regression: mpmc::Receiver<Dom>,
+    tx_history: mpmc::Receiver<Dom>,
+    tx_trend: mpmc::Receiver<Dom>,
     tx_input: mpmc::Receiver<Dom>,
 ) {
     log::trace!("app logic startup");
     let toggle_all_input = tx_input.recv().await.unwrap();
     let container_dom = tx_container.recv().await.unwrap();
+    let compare_dom = tx_compare.recv().await.unwrap();
+    let regression_dom = tx_regression.recv().await.unwrap();
+    let history_dom = tx_history.recv().await.unwrap();
+    let trend_dom = tx_trend.recv().await.unwrap();
+    show_regressions(&regression_dom, &app, &tx_logic);
 
+    let store = Store::open().await;
+    if let Err(err) = store.migrate().await {
+        log::error!("could not migrate the last run into the history: {:?}", err);
+    }
     // now that we have the test and results container, we can try to read
-    // any previous benchmarks and show them here.
-    if let Ok(benchmarks) = store::read_benchmarks() {
-        let graph = Component::from(graph::graph_benchmarks(&benchmarks)).build().unwrap();
-        container_dom.patch_children(ListPatch::push(graph.into_inner())).unwrap();
+    // the latest run and show it here.
+    let latest = store
+        .list_runs()
+        .await
+        .ok()
+        .and_then(|runs| runs.into_iter().next());
+    // The stored runs are read once and kept in step with the store here, so
+    // the trend doesn't have to read them all again on every change.
+    let mut trend_runs = read_trend_runs(&store).await;
+    show_trend(&trend_dom, &trend_runs, &app, &tx_logic);
+    let mut startup = vec![];
+    if let Some(latest) = latest {
+        startup.push(In::OpenRun(latest.id));
+    } else {
+        show_history(&store, &history_dom, None, &tx_logic).await;
+    }
+    if app.autorun {
+        startup.push(In::ClickedRun);
     }
 
-    while let Some(msg) = rx_logic.next().await {
+    let mut messages = futures::stream::iter(startup).chain(rx_logic);
+    while let Some(msg) = messages.next().await {
         match msg {
             In::AvgOverTimesChange {
                 changed_times,
@@ -184,6 +531,31 @@ async fn app_logic(
                 }
             }
 
+            In::WarmUpsChange(warm_ups) => {
+                app.warm_ups = warm_ups;
+            }
+
+            In::OutlierFencesChange(fences) => {
+                app.outlier_fences = fences;
+            }
+
+            In::TodoCountChange(todos) => {
+                app.bench_config = BenchConfig {
+                    wait: app.bench_config.wait,
+                    settle: app.bench_config.settle,
+                    scenarios: app.bench_config.scenarios,
+                    ..BenchConfig::with_todos(todos)
+                };
+            }
+
+            In::WaitStrategyChange(wait) => {
+                app.bench_config.wait = wait;
+            }
+
+            In::SettleChange(settle) => {
+                app.bench_config.settle = settle;
+            }
+
             In::SoloFramework(name) => {
                 for facade in app.cards.values() {
                     let card = facade.get_card().await;
@@ -205,32 +577,47 @@ async fn app_logic(
                         std::iter::once(bench_runner_view.into_inner()),
                     ))
                     .unwrap();
+                show_comparison(&compare_dom, &vec![]);
 
                 // Set all the cards to "ready"
                 for card in app.cards.values() {
                     card.set_state(FrameworkState::Ready).await;
                 }
 
-                // Gather all the frameworks we'll run
+                // Gather all the frameworks we'll run, paired with whether
+                // the run is a warm-up
+                let mut enabled = vec![];
+                for facade in app.cards.values() {
+                    let card: FrameworkCard = facade.get_card().await;
+                    if card.is_enabled {
+                        enabled.push(card);
+                    }
+                }
+                let mut rng = thread_rng();
                 let mut frameworks = vec![];
                 for _ in 1..=app.avg_times {
-                    let mut frameworks_run = vec![];
-                    for facade in app.cards.values() {
-                        let card: FrameworkCard = facade.get_card().await;
-                        if card.is_enabled {
-                            frameworks_run.push(card);
-                        }
-                    }
+                    let mut frameworks_run = enabled
+                        .iter()
+                        .map(|card| (card.clone(), false))
+                        .collect::<Vec<_>>();
                     // Randomize the order of that run
-                    let mut rng = thread_rng();
                     frameworks_run.shuffle(&mut rng);
                     frameworks.extend(frameworks_run);
                 }
+                // Frameworks are popped off the end, so warm-ups go last
+                for _ in 0..app.warm_ups {
+                    let mut warm_ups = enabled
+                        .iter()
+                        .map(|card| (card.clone(), true))
+                        .collect::<Vec<_>>();
+                    warm_ups.shuffle(&mut rng);
+                    frameworks.extend(warm_ups);
+                }
 
                 trace!("running frameworks");
                 let mut benchmarks = vec![];
                 tx_view.broadcast(Out::RunDisabled(true)).await.unwrap();
-                'bench_run: while let Some(next_framework) = frameworks.pop() {
+                'bench_run: while let Some((next_framework, is_warm_up)) = frameworks.pop() {
                     if let Some(facade) = app.cards.get(&next_framework.name) {
                         facade.set_state(FrameworkState::Running).await;
                     }
@@ -242,19 +629,27 @@ async fn app_logic(
                         .await
                         .unwrap();
 
-                    let complete = bench_runner_facade.run(next_framework.clone()).fuse();
+                    let complete = bench_runner_facade
+                        .run(next_framework.clone(), app.bench_config)
+                        .fuse();
                     pin_mut!(complete);
                     let cancel = rx_cancel.next().fuse();
                     pin_mut!(cancel);
 
                     futures::select! {
-                        benchmark = complete => {
-                            if let Some(msg) = benchmark.failed_message.as_ref() {
-                                if let Some(facade) = app.cards.get(&next_framework.name) {
+                        mut benchmark = complete => {
+                            if is_warm_up {
+                                benchmark.excluded = Some(Exclusion::WarmUp);
+                            }
+                            if let Some(facade) = app.cards.get(&next_framework.name) {
+                                if let Some(msg) = benchmark.failed_message.as_ref() {
                                     facade
                                         .set_state(FrameworkState::Erred(msg.to_string()))
                                         .await;
                                 }
+                                if let Some(sizes) = benchmark.sizes.as_ref() {
+                                    facade.set_sizes(Some(sizes.clone())).await;
+                                }
                             }
                             benchmarks.push(benchmark);
                         },
@@ -265,25 +660,219 @@ async fn app_logic(
                     };
                 }
 
+                bench_runner::exclude_outliers(&mut benchmarks, app.outlier_fences);
+                app.show_results(&benchmarks).await;
                 //// Write the benchmarks to local storage if possible
-                let _ = store::write_items(&benchmarks);
-                //// Graph them
-                let graph = Component::from(graph::graph_benchmarks(&benchmarks));
-                trace!("created the graph");
-                let graph = graph
-                    .build()
-                    .unwrap_or_else(|e| panic!("couldn't create the graph: {}", e))
-                    .into_inner();
+                let info = RunInfo::new(Some(app.bench_config));
+                match store.write_run(&info, &benchmarks).await {
+                    Ok(()) => {
+                        app.open_run = Some(info.id.clone());
+                        trend_runs.push((info, trend::run_times(&benchmarks)));
+                    }
+                    Err(err) => log::error!("could not store the run: {:?}", err),
+                }
+                show_history(&store, &history_dom, app.open_run.as_deref(), &tx_logic).await;
+                show_trend(&trend_dom, &trend_runs, &app, &tx_logic);
+                app.benchmarks = benchmarks.clone();
+                //// Graph them, removing the bench runner dom node
+                show_graph(&container_dom, &benchmarks, app.graph_mode);
                 trace!("built the graph");
-                // Remove the bench runner dom node and add the graph
-                container_dom
-                    .patch_children(ListPatch::splice(.., std::iter::once(graph)))
-                    .unwrap();
+                show_comparison(&compare_dom, &benchmarks);
+                show_regressions(&regression_dom, &app, &tx_logic);
 
                 trace!("done.");
                 tx_view.broadcast(Out::RunDisabled(false)).await.unwrap();
             }
 
+            In::ExportJson => {
+                let filename = app.export_filename("json");
+                let exported = export::to_json(&app.benchmarks)
+                    .map_err(JsValue::from)
+                    .and_then(|json| export::download(&filename, "application/json", &json));
+                if let Err(err) = exported {
+                    log::error!("could not export json: {:?}", err);
+                }
+            }
+
+            In::ExportCsv => {
+                let csv = export::to_csv(&app.benchmarks);
+                if let Err(err) = export::download(&app.export_filename("csv"), "text/csv", &csv) {
+                    log::error!("could not export csv: {:?}", err);
+                }
+            }
+
+            In::Share => {
+                let window = mogwai::utils::window();
+                match share::link(&app.benchmarks) {
+                    Ok(link) => {
+                        let _ = window.prompt_with_message_and_default("Copy this link", &link);
+                    }
+                    Err(err) => {
+                        log::error!("could not share the run: {}", err);
+                        let _ = window.alert_with_message(&err);
+                    }
+                }
+            }
+
+            In::MergeImportChange(merge) => {
+                app.merge_import = merge;
+            }
+
+            In::ImportFile(file) => {
+                let benchmarks = match app.import(&file).await {
+                    Ok(benchmarks) => benchmarks,
+                    Err(err) => {
+                        log::error!("{}", err);
+                        let _ = mogwai::utils::window().alert_with_message(&err);
+                        continue;
+                    }
+                };
+                show_graph(&container_dom, &benchmarks, app.graph_mode);
+                show_comparison(&compare_dom, &benchmarks);
+                app.show_results(&benchmarks).await;
+                app.benchmarks = benchmarks;
+                show_regressions(&regression_dom, &app, &tx_logic);
+                // Imported benchmarks aren't in the history
+                app.open_run = None;
+                show_history(&store, &history_dom, None, &tx_logic).await;
+            }
+
+            In::OpenRun(id) => {
+                let benchmarks = match store.read_run(&id).await {
+                    Ok(benchmarks) => benchmarks,
+                    Err(err) => {
+                        let msg = format!("could not read run {}: {:?}", id, err);
+                        log::error!("{}", msg);
+                        let _ = mogwai::utils::window().alert_with_message(&msg);
+                        continue;
+                    }
+                };
+                show_graph(&container_dom, &benchmarks, app.graph_mode);
+                show_comparison(&compare_dom, &benchmarks);
+                app.show_results(&benchmarks).await;
+                app.benchmarks = benchmarks;
+                show_regressions(&regression_dom, &app, &tx_logic);
+                app.open_run = Some(id);
+                show_history(&store, &history_dom, app.open_run.as_deref(), &tx_logic).await;
+            }
+
+            In::RenameRun(id, name) => {
+                match store.rename_run(&id, &name).await {
+                    Ok(()) => {
+                        for (info, _) in trend_runs.iter_mut().filter(|(info, _)| info.id == id) {
+                            info.name = name.clone();
+                        }
+                    }
+                    Err(err) => log::error!("could not rename run {}: {:?}", id, err),
+                }
+                show_history(&store, &history_dom, app.open_run.as_deref(), &tx_logic).await;
+                show_trend(&trend_dom, &trend_runs, &app, &tx_logic);
+            }
+
+            In::DeleteRun(id) => {
+                match store.delete_run(&id).await {
+                    Ok(()) => trend_runs.retain(|(info, _)| info.id != id),
+                    Err(err) => log::error!("could not delete run {}: {:?}", id, err),
+                }
+                if app.open_run.as_deref() == Some(id.as_str()) {
+                    // Take down everything that showed the deleted run
+                    app.open_run = None;
+                    app.benchmarks = vec![];
+                    container_dom
+                        .patch_children(ListPatch::splice(.., std::iter::empty()))
+                        .unwrap();
+                    show_comparison(&compare_dom, &app.benchmarks);
+                    app.show_results(&app.benchmarks).await;
+                    show_regressions(&regression_dom, &app, &tx_logic);
+                }
+                show_history(&store, &history_dom, app.open_run.as_deref(), &tx_logic).await;
+                show_trend(&trend_dom, &trend_runs, &app, &tx_logic);
+            }
+
+            In::SetBaseline(id) => {
+                let name = store
+                    .list_runs()
+                    .await
+                    .ok()
+                    .and_then(|runs| runs.into_iter().find(|run| run.id == id))
+                    .map(|run| run.name)
+                    .unwrap_or_else(|| format!("run {}", id));
+                match store.read_run(&id).await {
+                    Ok(benchmarks) => app.baseline = Some(Baseline { name, benchmarks }),
+                    Err(err) => {
+                        let msg = format!("could not read run {}: {:?}", id, err);
+                        log::error!("{}", msg);
+                        let _ = mogwai::utils::window().alert_with_message(&msg);
+                        continue;
+                    }
+                }
+                show_regressions(&regression_dom, &app, &tx_logic);
+            }
+
+            In::ImportBaseline(file) => {
+                match read_exported(&file).await {
+                    Ok(benchmarks) => {
+                        app.baseline = Some(Baseline {
+                            name: file.name(),
+                            benchmarks,
+                        })
+                    }
+                    Err(err) => {
+                        log::error!("{}", err);
+                        let _ = mogwai::utils::window().alert_with_message(&err);
+                        continue;
+                    }
+                }
+                show_regressions(&regression_dom, &app, &tx_logic);
+            }
+
+            In::ClearBaseline => {
+                app.baseline = None;
+                show_regressions(&regression_dom, &app, &tx_logic);
+            }
+
+            In::RegressionThresholdChange(threshold) => {
+                app.regression_threshold = threshold;
+                show_regressions(&regression_dom, &app, &tx_logic);
+            }
+
+            In::ExportReport => {
+                let baseline = match app.baseline.as_ref() {
+                    Some(baseline) => baseline,
+                    None => continue,
+                };
+                let shown = match app.open_run.as_ref() {
+                    Some(id) => store
+                        .list_runs()
+                        .await
+                        .ok()
+                        .and_then(|runs| runs.into_iter().find(|run| &run.id == id))
+                        .map(|run| run.name)
+                        .unwrap_or_else(|| format!("run {}", id)),
+                    None => "shown run".to_string(),
+                };
+                let report = Report::against_baseline(
+                    (baseline.name.clone(), baseline.benchmarks.clone()),
+                    &[(shown, app.benchmarks.clone())],
+                    app.regression_threshold,
+                );
+                let filename = app.export_filename("md");
+                let markdown = report.to_markdown();
+                if let Err(err) = export::download(&filename, "text/markdown", &markdown) {
+                    log::error!("could not export the report: {:?}", err);
+                }
+            }
+
+            In::GraphModeChange(mode) => {
+                app.graph_mode = mode;
+                show_graph(&container_dom, &app.benchmarks, app.graph_mode);
+            }
+
+            In::TrendStepChange(step) => {
+                app.trend_step = step;
+                show_trend(&trend_dom, &trend_runs, &app, &tx_logic);
+            }
+
             In::ToggleAll => {
                 let is_enabled = toggle_all_input
                     .visit_as(|input: &HtmlInputElement| input.checked(), |_| false)
@@ -303,10 +892,47 @@ fn app_view(
     rx: broadcast::Receiver<Out>,
 
     tx_container: mpmc::Sender<Dom>,
+    tx_compare: mpmc::Sender<Dom>,
+    tx_regression: mpmc::Sender<Dom>,
+    tx_history: mpmc::Sender<Dom>,
+    tx_trend: mpmc::Sender<Dom>,
     tx_input: mpmc::Sender<Dom>,
 
     cards: Vec<Component<Dom>>,
+    errors: Vec<String>,
 ) -> ViewBuilder<Dom> {
+    let todo_count_options = BenchConfig::TODO_COUNTS
+        .iter()
+        .map(|count| {
+            builder! {
+                <option
+                 value=format!("{}", count)
+                 boolean:selected=*count == app.bench_config.todos>
+                    {format!("{}", count)}
+                </option>
+            }
+        })
+        .collect::<Vec<_>>();
+    let warm_up_options = App::WARM_UPS
+        .iter()
+        .map(|count| {
+            builder! {
+                <option
+                 value=format!("{}", count)
+                 boolean:selected=*count == app.warm_ups>
+                    {format!("{}", count)}
+                </option>
+            }
+        })
+        .collect::<Vec<_>>();
+    let error_alerts = errors
+        .into_iter()
+        .map(|err| {
+            builder! {
+                <div class="alert alert-danger" role="alert">{err}</div>
+            }
+        })
+        .collect::<Vec<_>>();
     builder! {
         <div id="main" class="container-fluid">
             <nav class="navbar navbar-expand-lg navbar-light bg-light rounded-sm mt-2 mb-4">
@@ -341,6 +967,84 @@ fn app_view(
                         </span>
                     </li>
                 </ul>
+                <div class="input-group col-2">
+                    <div class="input-group-prepend">
+                        <span class="input-group-text">"todos"</span>
+                    </div>
+                    <select
+                     class="custom-select"
+                     on:change = tx.sink().contra_filter_map(|event: Event| {
+                         In::from_todo_count_change_event(event)
+                     })>
+                        {todo_count_options}
+                    </select>
+                </div>
+                <div class="input-group col-2">
+                    <div class="input-group-prepend">
+                        <span class="input-group-text">"wait by"</span>
+                    </div>
+                    <select
+                     class="custom-select"
+                     on:change = tx.sink().contra_filter_map(|event: Event| {
+                         In::from_wait_strategy_change_event(event)
+                     })>
+                        <option value="poll" boolean:selected=app.bench_config.wait == WaitStrategy::Poll>
+                            "polling"
+                        </option>
+                        <option value="mutation" boolean:selected=app.bench_config.wait == WaitStrategy::Mutation>
+                            "mutations"
+                        </option>
+                    </select>
+                </div>
+                <div class="input-group col-2">
+                    <div class="input-group-prepend">
+                        <span class="input-group-text">"end at"</span>
+                    </div>
+                    <select
+                     class="custom-select"
+                     on:change = tx.sink().contra_filter_map(|event: Event| {
+                         In::from_settle_change_event(event)
+                     })>
+                        <option value="dom" boolean:selected=!app.bench_config.settle>
+                            "dom ready"
+                        </option>
+                        <option value="paint" boolean:selected=app.bench_config.settle>
+                            "paint"
+                        </option>
+                    </select>
+                </div>
+                <div class="input-group col-1">
+                    <div class="input-group-prepend">
+                        <span class="input-group-text">"warm up"</span>
+                    </div>
+                    <select
+                     class="custom-select"
+                     on:change = tx.sink().contra_filter_map(|event: Event| {
+                         In::from_warm_ups_change_event(event)
+                     })>
+                        {warm_up_options}
+                    </select>
+                </div>
+                <div class="input-group col-2">
+                    <div class="input-group-prepend">
+                        <span class="input-group-text">"outliers"</span>
+                    </div>
+                    <select
+                     class="custom-select"
+                     on:change = tx.sink().contra_filter_map(|event: Event| {
+                         In::from_outlier_fences_change_event(event)
+                     })>
+                        <option value="off" boolean:selected=app.outlier_fences == OutlierFences::Off>
+                            "keep all"
+                        </option>
+                        <option value="inner" boolean:selected=app.outlier_fences == OutlierFences::Inner>
+                            "drop past 1.5 IQR"
+                        </option>
+                        <option value="outer" boolean:selected=app.outlier_fences == OutlierFences::Outer>
+                            "drop past 3 IQR"
+                        </option>
+                    </select>
+                </div>
                 <div class="input-group col-2">
                     <div class="input-group-prepend">
                         <span class="input-group-text">"avg over"</span>
@@ -349,6 +1053,7 @@ fn app_view(
                         type="text"
                         class="form-control"
                         placeholder="1"
+                        value=format!("{}", app.avg_times)
                         on:change = tx.sink().contra_map(|event: Event| In::from_repititions_change_event(event))
                         on:keyup = tx.sink().contra_filter_map(|event: web_sys::Event| {
                             let key_event = event.dyn_ref::<KeyboardEvent>()?;
@@ -388,11 +1093,121 @@ fn app_view(
                         </button>
                     </div>
                 </div>
+                <div class="btn-group ml-2">
+                    <button
+                     class="btn btn-outline-secondary"
+                     on:click=tx.sink().contra_map(|_| In::ExportJson)
+                     boolean:disabled=rx.clone().filter_map(|msg| async move {
+                         match msg {
+                             Out::RunDisabled(disabled) => Some(disabled),
+                             _ => None,
+                         }
+                     })>
+                        "Export JSON"
+                    </button>
+                    <button
+                     class="btn btn-outline-secondary"
+                     on:click=tx.sink().contra_map(|_| In::ExportCsv)
+                     boolean:disabled=rx.clone().filter_map(|msg| async move {
+                         match msg {
+                             Out::RunDisabled(disabled) => Some(disabled),
+                             _ => None,
+                         }
+                     })>
+                        "Export CSV"
+                    </button>
+                    <button
+                     class="btn btn-outline-secondary"
+                     title="get a link showing this run"
+                     on:click=tx.sink().contra_map(|_| In::Share)
+                     boolean:disabled=rx.clone().filter_map(|msg| async move {
+                         match msg {
+                             Out::RunDisabled(disabled) => Some(disabled),
+                             _ => None,
+                         }
+                     })>
+                        "Share"
+                    </button>
+                </div>
+                <div class="input-group col-2 ml-2">
+                    <div class="input-group-prepend">
+                        <label
+                         class="input-group-text mb-0"
+                         title="show imported runs alongside the shown run instead of replacing it">
+                            <input
+                             type="checkbox"
+                             class="mr-1"
+                             on:change = tx.sink().contra_filter_map(|event: Event| {
+                                 In::from_merge_import_change_event(event)
+                             })
+                            />
+                            "merge"
+                        </label>
+                    </div>
+                    <input
+                     type="file"
+                     class="form-control"
+                     accept=".json,application/json"
+                     title="import exported JSON"
+                     on:change = tx.sink().contra_filter_map(|event: Event| {
+                         In::from_import_file_event(event)
+                     })
+                    />
+                </div>
             </nav>
             <div class="container">
+                {error_alerts}
+                <div class="row mb-2">
+                    <div class="input-group input-group-sm col-3 pl-0">
+                        <div class="input-group-prepend">
+                            <span class="input-group-text">"graph"</span>
+                        </div>
+                        <select
+                         class="custom-select"
+                         on:change = tx.sink().contra_filter_map(|event: Event| {
+                             In::from_graph_mode_change_event(event)
+                         })>
+                            <option value="timeline" boolean:selected=app.graph_mode == GraphMode::Timeline>
+                                "timeline"
+                            </option>
+                            <option value="stacked" boolean:selected=app.graph_mode == GraphMode::Stacked>
+                                "stacked steps"
+                            </option>
+                            <option
+                             value="box"
+                             title="the spread of each step when averaging over more than one run"
+                             boolean:selected=app.graph_mode == GraphMode::Box>
+                                "box plots"
+                            </option>
+                        </select>
+                    </div>
+                </div>
                 <div class="row embed-responsive embed-responsive-16by9 mb-4"
                     post:build = move |dom: &mut Dom| tx_container.try_send(dom.clone()).unwrap()>
                 </div>
+                <div post:build = move |dom: &mut Dom| tx_compare.try_send(dom.clone()).unwrap()>
+                </div>
+                <div class="row mb-4">
+                    <h5>"Regressions"</h5>
+                    <div
+                     class="w-100"
+                     post:build = move |dom: &mut Dom| tx_regression.try_send(dom.clone()).unwrap()>
+                    </div>
+                </div>
+                <div class="row mb-4">
+                    <h5>"History"</h5>
+                    <div
+                     class="w-100"
+                     post:build = move |dom: &mut Dom| tx_history.try_send(dom.clone()).unwrap()>
+                    </div>
+                </div>
+                <div class="row mb-4">
+                    <h5>"Trend"</h5>
+                    <div
+                     class="w-100"
+                     post:build = move |dom: &mut Dom| tx_trend.try_send(dom.clone()).unwrap()>
+                    </div>
+                </div>
                 <div class="row mb-4 embed-responsive">
                     <table class="table table-bordered">
                         <thead>
@@ -411,6 +1226,7 @@ fn app_view(
                                 <th scope="col">"vDOM"</th>
                                 <th scope="col">"Size"</th>
                                 <th scope="col">"Score"</th>
+                                <th scope="col">"Total"</th>
                                 <th scope="col">"Note"</th>
                             </tr>
                         </thead>
@@ -424,8 +1240,12 @@ fn app_view(
     }
 }
 
-pub fn app_component() -> Component<Dom> {
-    let (card_facades, card_components): (Vec<(String, _)>, Vec<_>) = framework_card::all_cards()
+pub fn app_component(
+    cards: Vec<FrameworkCard>,
+    preset: Preset,
+    errors: Vec<String>,
+) -> Component<Dom> {
+    let (card_facades, card_components): (Vec<(String, _)>, Vec<_>) = cards
         .into_iter()
         .map(|card| {
             let name = card.name.clone();
@@ -435,11 +1255,26 @@ pub fn app_component() -> Component<Dom> {
         .unzip();
     let app = App {
         cards: card_facades.into_iter().collect::<HashMap<_, _>>(),
-        avg_times: 1,
+        avg_times: preset.times.unwrap_or(1),
+        warm_ups: 0,
+        outlier_fences: OutlierFences::Off,
+        bench_config: preset.bench_config(),
+        open_run: None,
+        benchmarks: vec![],
+        merge_import: false,
+        autorun: preset.autorun,
+        baseline: None,
+        regression_threshold: report::DEFAULT_THRESHOLD,
+        trend_step: None,
+        graph_mode: GraphMode::default(),
     };
     let (tx_logic, rx_logic) = broadcast::bounded(1);
     let (tx_view, rx_view) = broadcast::bounded(1);
     let (tx_container, rx_container) = mpmc::bounded(1);
+    let (tx_compare, rx_compare) = mpmc::bounded(1);
+    let (tx_regression, rx_regression) = mpmc::bounded(1);
+    let (tx_history, rx_history) = mpmc::bounded(1);
+    let (tx_trend, rx_trend) = mpmc::bounded(1);
     let (tx_input, rx_input) = mpmc::bounded(1);
     let (tx_cancel, rx_cancel) = broadcast::bounded(1);
 
@@ -449,8 +1284,13 @@ pub fn app_component() -> Component<Dom> {
         tx_cancel,
         rx_view,
         tx_container,
+        tx_compare,
+        tx_regression,
+        tx_history,
+        tx_trend,
         tx_input,
         card_components,
+        errors,
     ))
     .with_logic(app_logic(
         app,
@@ -459,14 +1299,53 @@ pub fn app_component() -> Component<Dom> {
         rx_cancel,
         tx_view,
         rx_container,
+        rx_compare,
+        rx_regression,
+        rx_history,
+        rx_trend,
         rx_input,
     ))
 }
 
 #[wasm_bindgen]
-pub fn bench() -> Result<(), JsValue> {
+pub async fn bench() -> Result<(), JsValue> {
     panic::set_hook(Box::new(console_error_panic_hook::hook));
     console_log::init_with_level(Level::Trace).unwrap();
 
-    app_component().build().unwrap().run()
+    let mut errors = vec![];
+    match share::from_location() {
+        Some(Ok(benchmarks)) => {
+            return share::shared_component(benchmarks).build().unwrap().run();
+        }
+        Some(Err(err)) => errors.push(err),
+        None => {}
+    }
+
+    let search = mogwai::utils::window()
+        .location()
+        .search()
+        .unwrap_or_default();
+    let (mut preset, preset_errors) = Preset::parse(&search);
+    errors.extend(preset_errors);
+
+    let mut cards = match manifest::load(manifest::MANIFEST_URL).await {
+        Ok(manifest) => {
+            errors.extend(manifest.errors);
+            manifest.cards
+        }
+        Err(err) => {
+            errors.push(err);
+            vec![]
+        }
+    };
+    errors.extend(preset.apply_to_cards(&mut cards));
+    if preset.autorun && !errors.is_empty() {
+        // Don't start a run that isn't what was asked for.
+        preset.autorun = false;
+    }
+    for err in errors.iter() {
+        log::error!("{}", err);
+    }
+
+    app_component(cards, preset, errors).build().unwrap().run()
 }
@@ -0,0 +1,275 @@
+//! Parses the frameworks manifest (see `frameworks.schema.json`) into
+//! `FrameworkCard`s.
+use std::{collections::HashSet, fmt};
+
+use serde::{
+    de::{MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use serde_json::value::RawValue;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::Response;
+
+use super::framework_card::{CreateTodoMethod, FrameworkCard, FrameworkState};
+
+/// Where the app looks for the manifest, relative to index.html.
+pub const MANIFEST_URL: &str = "frameworks.json";
+
+#[derive(Deserialize)]
+struct RawManifest {
+    // Kept raw so each entry can fail on its own, and so the attributes are
+    // read in the order they're written
+    frameworks: Vec<Box<RawValue>>,
+}
+
+/// Just enough of an entry to name it in an error.
+#[derive(Deserialize)]
+struct EntryName {
+    name: Option<String>,
+}
+
+/// Read a JSON object of strings in the order it's written, which is the
+/// order the card lists them in.
+fn ordered_attributes<'de, D>(deserializer: D) -> Result<Vec<(String, String)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct Pairs;
+
+    impl<'de> Visitor<'de> for Pairs {
+        type Value = Vec<(String, String)>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a map of attribute names to strings")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut pairs = vec![];
+            while let Some(pair) = map.next_entry()? {
+                pairs.push(pair);
+            }
+            Ok(pairs)
+        }
+    }
+
+    deserializer.deserialize_map(Pairs)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestEntry {
+    name: String,
+    url: String,
+    #[serde(deserialize_with = "ordered_attributes")]
+    attributes: Vec<(String, String)>,
+    create_todo_method: CreateTodoMethod,
+    #[serde(default)]
+    wait_for_input_focus: bool,
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ManifestEntry {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name is empty".into());
+        }
+        if self.url.trim().is_empty() {
+            return Err("url is empty".into());
+        }
+        let mut names = self.attributes.iter().map(|(name, _)| name);
+        for key in ["language", "version"].iter() {
+            if !names.clone().any(|name| name == key) {
+                return Err(format!("missing '{}' attribute", key));
+            }
+        }
+        if !names.any(|name| name.contains("vdom")) {
+            return Err("missing a 'vdom' attribute".into());
+        }
+        Ok(())
+    }
+
+    fn into_card(self) -> FrameworkCard {
+        FrameworkCard {
+            name: self.name,
+            url: self.url,
+            attributes: self.attributes,
+            is_enabled: self.enabled,
+            state: FrameworkState::Ready,
+            create_todo_method: self.create_todo_method,
+            wait_for_input_focus: self.wait_for_input_focus,
+        }
+    }
+}
+
+/// The good cards of a manifest along with a message for each bad entry.
+pub struct Manifest {
+    pub cards: Vec<FrameworkCard>,
+    pub errors: Vec<String>,
+}
+
+/// Parse a manifest. Bad entries are skipped and reported in
+/// `Manifest::errors`, only a malformed document is an `Err`.
+pub fn parse(json: &str) -> Result<Manifest, String> {
+    let raw: RawManifest =
+        serde_json::from_str(json).map_err(|e| format!("malformed frameworks manifest: {}", e))?;
+
+    let mut names = HashSet::new();
+    let mut manifest = Manifest {
+        cards: vec![],
+        errors: vec![],
+    };
+    for (i, value) in raw.frameworks.into_iter().enumerate() {
+        let label = serde_json::from_str::<EntryName>(value.get())
+            .ok()
+            .and_then(|entry| entry.name)
+            .map(|name| format!("framework {} ('{}')", i, name))
+            .unwrap_or_else(|| format!("framework {}", i));
+        let entry = serde_json::from_str::<ManifestEntry>(value.get())
+            .map_err(|e| e.to_string())
+            .and_then(|entry| entry.validate().map(|_| entry));
+        match entry {
+            Ok(entry) if !names.insert(entry.name.clone()) => {
+                manifest.errors.push(format!("{}: duplicate name", label));
+            }
+            Ok(entry) => manifest.cards.push(entry.into_card()),
+            Err(err) => manifest.errors.push(format!("{}: {}", label, err)),
+        }
+    }
+    Ok(manifest)
+}
+
+/// Fetch and parse the manifest at the given url.
+pub async fn load(url: &str) -> Result<Manifest, String> {
+    let fetch_err = |e: JsValue| format!("could not fetch {}: {:?}", url, e);
+    let response = JsFuture::from(mogwai::utils::window().fetch_with_str(url))
+        .await
+        .map_err(fetch_err)?
+        .dyn_into::<Response>()
+        .map_err(fetch_err)?;
+    if !response.ok() {
+        return Err(format!(
+            "could not fetch {}: {} {}",
+            url,
+            response.status(),
+            response.status_text()
+        ));
+    }
+    let text = JsFuture::from(response.text().map_err(fetch_err)?)
+        .await
+        .map_err(fetch_err)?
+        .as_string()
+        .ok_or_else(|| format!("{} is not text", url))?;
+    parse(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_shipped_manifest() {
+        let manifest = parse(include_str!("../../frameworks.json")).unwrap();
+        assert!(manifest.errors.is_empty(), "{:?}", manifest.errors);
+        assert!(!manifest.cards.is_empty());
+    }
+
+    #[test]
+    fn applies_defaults() {
+        let manifest = parse(
+            r#"{"frameworks": [{
+                "name": "mogwai",
+                "url": "frameworks/mogwai/index.html",
+                "attributes": {"language": "rust", "version": "0.5", "has vdom": "no"},
+                "create_todo_method": "input_and_keydown"
+            }]}"#,
+        )
+        .unwrap();
+        assert!(manifest.errors.is_empty());
+        let card = &manifest.cards[0];
+        assert!(card.is_enabled);
+        assert!(!card.wait_for_input_focus);
+        assert!(matches!(
+            card.create_todo_method,
+            CreateTodoMethod::InputAndKeydown
+        ));
+        assert_eq!(card.framework_attribute("version"), Some("0.5".to_string()));
+    }
+
+    #[test]
+    fn keeps_the_attribute_order() {
+        let manifest = parse(
+            r#"{"frameworks": [{
+                "name": "mogwai",
+                "url": "frameworks/mogwai/index.html",
+                "attributes": {"version": "0.5", "language": "rust", "has vdom": "no"},
+                "create_todo_method": "change"
+            }]}"#,
+        )
+        .unwrap();
+        let names = manifest.cards[0]
+            .attributes
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["version", "language", "has vdom"]);
+    }
+
+    #[test]
+    fn reports_bad_entries_and_keeps_good_ones() {
+        let manifest = parse(
+            r#"{"frameworks": [
+                {
+                    "name": "good",
+                    "url": "good.html",
+                    "attributes": {"language": "js", "version": "1", "has vdom": "yes"},
+                    "create_todo_method": "change",
+                    "enabled": false
+                },
+                {
+                    "name": "unknown method",
+                    "url": "a.html",
+                    "attributes": {"language": "js", "version": "1", "has vdom": "yes"},
+                    "create_todo_method": "telepathy"
+                },
+                {
+                    "name": "no version",
+                    "url": "b.html",
+                    "attributes": {"language": "js", "has vdom": "yes"},
+                    "create_todo_method": "change"
+                },
+                {
+                    "name": "good",
+                    "url": "c.html",
+                    "attributes": {"language": "js", "version": "1", "has vdom": "yes"},
+                    "create_todo_method": "change"
+                },
+                {
+                    "url": "d.html",
+                    "attributes": {"language": "js", "version": "1", "has vdom": "yes"},
+                    "create_todo_method": "change",
+                    "typo": true
+                }
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.cards.len(), 1);
+        assert!(!manifest.cards[0].is_enabled);
+        assert_eq!(manifest.errors.len(), 4, "{:?}", manifest.errors);
+        assert!(manifest.errors[0].starts_with("framework 1 ('unknown method')"));
+        assert!(manifest.errors[1].contains("missing 'version' attribute"));
+        assert!(manifest.errors[2].contains("duplicate name"));
+        assert!(manifest.errors[3].starts_with("framework 4:"));
+    }
+
+    #[test]
+    fn rejects_a_malformed_document() {
+        assert!(parse("[]").is_err());
+        assert!(parse("{\"frameworks\": ").is_err());
+    }
+}
@@ -0,0 +1,208 @@
+//! Configures the bench from the page's query string, so a run can be set up
+//! (and started) by a link, eg.
+//! `?frameworks=mogwai%200.5,React&times=5&todos=1000&scenarios=edit,filter&autorun=1`.
+use super::{
+    bench_runner::{BenchConfig, Scenarios},
+    framework_card::FrameworkCard,
+};
+
+/// Settings read from the query string. Anything not given keeps its default.
+#[derive(Debug, Default, PartialEq)]
+pub struct Preset {
+    /// The only frameworks to enable.
+    pub frameworks: Option<Vec<String>>,
+    pub times: Option<u32>,
+    pub todos: Option<usize>,
+    pub scenarios: Option<Scenarios>,
+    /// Start a run as soon as the page is ready.
+    pub autorun: bool,
+}
+
+/// Decode a percent-encoded query string component.
+fn decode(component: &str) -> Result<String, String> {
+    let mut bytes = vec![];
+    let mut iter = component.bytes();
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [iter.next(), iter.next()];
+                let decoded = match hex {
+                    [Some(hi), Some(lo)] => std::str::from_utf8(&[hi, lo])
+                        .ok()
+                        .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+                    _ => None,
+                };
+                bytes.push(decoded.ok_or_else(|| format!("bad escape in '{}'", component))?);
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| format!("'{}' is not utf-8", component))
+}
+
+fn list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn parse_times(value: &str) -> Result<u32, String> {
+    match value.trim().parse::<u32>() {
+        Ok(times) if times > 0 => Ok(times),
+        _ => Err(format!("'{}' is not a positive number", value)),
+    }
+}
+
+fn parse_todos(value: &str) -> Result<usize, String> {
+    value
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .filter(|todos| BenchConfig::TODO_COUNTS.contains(todos))
+        .ok_or_else(|| {
+            format!(
+                "'{}' is not one of {}",
+                value,
+                BenchConfig::TODO_COUNTS
+                    .iter()
+                    .map(|count| count.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+}
+
+fn parse_scenarios(value: &str) -> Result<Scenarios, String> {
+    let mut scenarios = Scenarios::none();
+    for name in list(value) {
+        let enabled = scenarios
+            .get_mut(&name)
+            .ok_or_else(|| format!("'{}' is not one of {}", name, Scenarios::NAMES.join(", ")))?;
+        *enabled = true;
+    }
+    Ok(scenarios)
+}
+
+fn parse_flag(value: &str) -> Result<bool, String> {
+    match value.trim() {
+        "" | "1" | "true" => Ok(true),
+        "0" | "false" => Ok(false),
+        _ => Err(format!("'{}' is not 1, 0, true or false", value)),
+    }
+}
+
+impl Preset {
+    /// Read a query string, with or without its leading '?'. Parameters that
+    /// can't be read are left out of the preset and reported.
+    pub fn parse(query: &str) -> (Preset, Vec<String>) {
+        let mut preset = Preset::default();
+        let mut errors = vec![];
+        let pairs = query
+            .trim_start_matches('?')
+            .split('&')
+            .filter(|pair| !pair.is_empty());
+        for pair in pairs {
+            let mut split = pair.splitn(2, '=');
+            let key = split.next().unwrap_or_default();
+            let value = split.next().unwrap_or_default();
+            let result = decode(key).and_then(|key| {
+                let value = decode(value)?;
+                match key.as_str() {
+                    "frameworks" => preset.frameworks = Some(list(&value)),
+                    "times" => preset.times = Some(parse_times(&value)?),
+                    "todos" => preset.todos = Some(parse_todos(&value)?),
+                    "scenarios" => preset.scenarios = Some(parse_scenarios(&value)?),
+                    "autorun" => preset.autorun = parse_flag(&value)?,
+                    _ => return Err("unknown parameter".into()),
+                }
+                Ok(())
+            });
+            if let Err(err) = result {
+                errors.push(format!("query parameter '{}': {}", key, err));
+            }
+        }
+        (preset, errors)
+    }
+
+    /// Enable only the preset frameworks, if any were given. Names that don't
+    /// match a framework are reported.
+    pub fn apply_to_cards(&self, cards: &mut [FrameworkCard]) -> Vec<String> {
+        let names = match self.frameworks.as_ref() {
+            Some(names) => names,
+            None => return vec![],
+        };
+        for card in cards.iter_mut() {
+            card.is_enabled = names.contains(&card.name);
+        }
+        names
+            .iter()
+            .filter(|name| !cards.iter().any(|card| &card.name == *name))
+            .map(|name| {
+                format!(
+                    "query parameter 'frameworks': there is no framework '{}'",
+                    name
+                )
+            })
+            .collect()
+    }
+
+    /// The bench config with the preset todo count and scenarios.
+    pub fn bench_config(&self) -> BenchConfig {
+        let config = self.todos.map(BenchConfig::with_todos).unwrap_or_default();
+        BenchConfig {
+            scenarios: self.scenarios.unwrap_or_default(),
+            ..config
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_every_parameter() {
+        let (preset, errors) = Preset::parse(
+            "?frameworks=mogwai%200.5,React&times=5&todos=1000&scenarios=edit,toggle_all&autorun=1",
+        );
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            preset.frameworks,
+            Some(vec!["mogwai 0.5".to_string(), "React".to_string()])
+        );
+        assert_eq!(preset.times, Some(5));
+        assert_eq!(preset.bench_config().todos, 1000);
+        let scenarios = preset.scenarios.unwrap();
+        assert_eq!(scenarios.enabled(), vec!["edit", "toggle_all"]);
+        assert!(preset.autorun);
+    }
+
+    #[test]
+    fn defaults_what_is_not_given() {
+        let (preset, errors) = Preset::parse("");
+        assert!(errors.is_empty());
+        assert_eq!(preset, Preset::default());
+        assert_eq!(preset.bench_config().scenarios, Scenarios::default());
+    }
+
+    #[test]
+    fn reports_invalid_parameters() {
+        let (preset, errors) =
+            Preset::parse("times=0&todos=7&scenarios=edit,fly&autorun=maybe&colour=blue&a=%zz");
+        assert_eq!(preset, Preset::default());
+        assert_eq!(
+            errors,
+            vec![
+                "query parameter 'times': '0' is not a positive number",
+                "query parameter 'todos': '7' is not one of 10, 100, 1000, 10000",
+                "query parameter 'scenarios': 'fly' is not one of edit, filter, toggle_all, complete",
+                "query parameter 'autorun': 'maybe' is not 1, 0, true or false",
+                "query parameter 'colour': unknown parameter",
+                "query parameter 'a': bad escape in '%zz'",
+            ]
+        );
+    }
+}
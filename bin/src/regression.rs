@@ -0,0 +1,135 @@
+//! Flags the steps of the shown run that got significantly slower than a
+//! baseline run, picked from the history or imported.
+use mogwai::prelude::*;
+
+use todo_mvc_bench_core::report::{self, StepDiff};
+
+use super::{bench_runner::Benchmark, In};
+
+/// A run the shown run is compared with.
+pub struct Baseline {
+    pub name: String,
+    pub benchmarks: Vec<Benchmark>,
+}
+
+fn diff_row(diff: &StepDiff) -> ViewBuilder<Dom> {
+    let p = diff
+        .p
+        .map(|p| format!("{:.3}", p))
+        .unwrap_or_else(|| "-".to_string());
+    builder! {
+        <tr class=if diff.regressed {
+                "table-danger"
+            } else if diff.too_few_samples {
+                "table-warning"
+            } else {
+                ""
+            }>
+            <td>{&diff.framework}</td>
+            <td>{&diff.step}</td>
+            <td>{format!("{:.1}ms", diff.before_median)}</td>
+            <td>{format!("{:.1}ms", diff.after_median)}</td>
+            <td>{format!("{:+.1}%", (diff.ratio - 1.0) * 100.0)}</td>
+            <td>{p}</td>
+        </tr>
+    }
+}
+
+/// A table of each step of the shown benchmarks against the baseline, with
+/// the controls to pick a baseline and a threshold.
+pub fn regression_view(
+    baseline: Option<&Baseline>,
+    benchmarks: &[Benchmark],
+    threshold: f64,
+    tx: &broadcast::Sender<In>,
+) -> ViewBuilder<Dom> {
+    let diffs = baseline
+        .map(|baseline| report::diff(&baseline.benchmarks, benchmarks, threshold))
+        .unwrap_or_default();
+    let regressions = diffs.iter().filter(|diff| diff.regressed).count();
+    let too_few_samples = diffs.iter().filter(|diff| diff.too_few_samples).count();
+    let status = match baseline {
+        None => "Pick a baseline from the history, or import one.".to_string(),
+        Some(baseline) if diffs.is_empty() => {
+            format!(
+                "The shown run has no steps in common with '{}'.",
+                baseline.name
+            )
+        }
+        Some(baseline) if too_few_samples > 0 => format!(
+            "{} regressions against '{}', and {} slower steps with too few samples to tell, \
+             average over at least 4 runs of each.",
+            regressions, baseline.name, too_few_samples
+        ),
+        Some(baseline) => format!("{} regressions against '{}'.", regressions, baseline.name),
+    };
+    let table = if diffs.is_empty() {
+        vec![]
+    } else {
+        let rows = diffs.iter().map(diff_row).collect::<Vec<_>>();
+        vec![builder! {
+            <table class="table table-sm">
+                <thead>
+                    <tr>
+                        <th scope="col">"Framework"</th>
+                        <th scope="col">"Step"</th>
+                        <th scope="col">"Baseline"</th>
+                        <th scope="col">"Shown"</th>
+                        <th scope="col">"Change"</th>
+                        <th scope="col" title="Mann-Whitney U test of the two runs' samples">"p"</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {rows}
+                </tbody>
+            </table>
+        }]
+    };
+    builder! {
+        <div class="w-100">
+            <div class="form-inline mb-2">
+                <span class="mr-2">{status}</span>
+                <div class="input-group input-group-sm mr-2">
+                    <div class="input-group-prepend">
+                        <span class="input-group-text">"regressed past"</span>
+                    </div>
+                    <input
+                     type="number"
+                     min="0"
+                     step="1"
+                     class="form-control"
+                     value=format!("{:.1}", threshold * 100.0)
+                     on:change = tx.sink().contra_filter_map(|event: Event| {
+                         In::from_regression_threshold_event(event)
+                     })
+                    />
+                    <div class="input-group-append">
+                        <span class="input-group-text">"%"</span>
+                    </div>
+                </div>
+                <input
+                 type="file"
+                 class="form-control form-control-sm mr-2"
+                 accept=".json,application/json"
+                 title="import a baseline from exported JSON"
+                 on:change = tx.sink().contra_filter_map(|event: Event| {
+                     In::from_import_baseline_event(event)
+                 })
+                />
+                <button
+                 class="btn btn-sm btn-outline-secondary mr-1"
+                 boolean:disabled=baseline.is_none()
+                 on:click = tx.sink().contra_map(|_| In::ExportReport)>
+                    "Export report"
+                </button>
+                <button
+                 class="btn btn-sm btn-outline-danger"
+                 boolean:disabled=baseline.is_none()
+                 on:click = tx.sink().contra_map(|_| In::ClearBaseline)>
+                    "Clear"
+                </button>
+            </div>
+            {table}
+        </div>
+    }
+}
@@ -0,0 +1,160 @@
+//! Composite framework scores.
+//!
+//! Like js-framework-benchmark, a framework's score is the geometric mean of
+//! its step durations, each normalized to the fastest framework's duration
+//! for that step:
+//!
+//! ```text
+//! score(f) = (Π t(f, s) / min_g t(g, s)) ^ (1 / n)   for each step s of f
+//! ```
+//!
+//! where `t(f, s)` is the mean duration of step `s` over all of `f`'s
+//! successful, non-excluded runs and `n` is the number of steps `f`
+//! completed. A score of 1.0 means the framework was the fastest at every
+//! step, 2.0 means it was twice as slow as the fastest on (geometric)
+//! average. Lower is better.
+use std::collections::HashMap;
+
+use super::bench_runner::Benchmark;
+
+/// Mean step durations keyed by framework name, then step name.
+fn mean_step_durations(benchmarks: &[Benchmark]) -> HashMap<String, HashMap<String, f64>> {
+    let mut sums: HashMap<String, HashMap<String, (f64, u32)>> = HashMap::new();
+    for benchmark in benchmarks.iter() {
+        if !benchmark.is_sample() {
+            continue;
+        }
+        let steps = sums.entry(benchmark.name.clone()).or_default();
+        for step in benchmark.steps.iter() {
+            if let Some(duration) = step.duration() {
+                let (sum, count) = steps.entry(step.name.clone()).or_insert((0.0, 0));
+                *sum += duration;
+                *count += 1;
+            }
+        }
+    }
+
+    sums.into_iter()
+        .map(|(name, steps)| {
+            let means = steps
+                .into_iter()
+                .map(|(step, (sum, count))| (step, sum / count as f64))
+                .collect();
+            (name, means)
+        })
+        .collect()
+}
+
+/// Score every framework that has at least one successful run. See the
+/// module docs for the formula.
+pub fn scores(benchmarks: &[Benchmark]) -> HashMap<String, f64> {
+    let durations = mean_step_durations(benchmarks);
+
+    let mut fastest: HashMap<&str, f64> = HashMap::new();
+    for steps in durations.values() {
+        for (step, duration) in steps.iter() {
+            let min = fastest.entry(step.as_str()).or_insert(f64::INFINITY);
+            *min = f64::min(*min, *duration);
+        }
+    }
+
+    durations
+        .iter()
+        .filter_map(|(name, steps)| {
+            // Steps that took no measurable time can't be normalized.
+            let ratios = steps
+                .iter()
+                .filter(|(step, duration)| **duration > 0.0 && fastest[step.as_str()] > 0.0)
+                .map(|(step, duration)| duration / fastest[step.as_str()])
+                .collect::<Vec<_>>();
+            if ratios.is_empty() {
+                return None;
+            }
+            let log_sum: f64 = ratios.iter().map(|ratio| ratio.ln()).sum();
+            Some((name.clone(), (log_sum / ratios.len() as f64).exp()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench_runner::Exclusion;
+
+    fn benchmark(name: &str, steps: &[(&str, f64)]) -> Benchmark {
+        Benchmark::sequential(name, steps)
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn fastest_everywhere_scores_one() {
+        let scores = scores(&[
+            benchmark("a", &[("load", 10.0), ("create", 100.0)]),
+            benchmark("b", &[("load", 20.0), ("create", 200.0)]),
+        ]);
+        assert_close(scores["a"], 1.0);
+        assert_close(scores["b"], 2.0);
+    }
+
+    #[test]
+    fn is_the_geometric_mean_of_normalized_steps() {
+        let scores = scores(&[
+            benchmark("a", &[("load", 10.0), ("create", 400.0)]),
+            benchmark("b", &[("load", 10.0), ("create", 100.0)]),
+        ]);
+        // a is 1x at load and 4x at create
+        assert_close(scores["a"], 2.0);
+        assert_close(scores["b"], 1.0);
+    }
+
+    #[test]
+    fn averages_repeated_runs() {
+        let scores = scores(&[
+            benchmark("a", &[("load", 10.0)]),
+            benchmark("a", &[("load", 30.0)]),
+            benchmark("b", &[("load", 40.0)]),
+        ]);
+        assert_close(scores["a"], 1.0);
+        assert_close(scores["b"], 2.0);
+    }
+
+    #[test]
+    fn ignores_failed_runs() {
+        let mut failed = benchmark("a", &[("load", 1.0)]);
+        failed.failed_message = Some("todo input not found".into());
+        let scores = scores(&[
+            failed,
+            benchmark("b", &[("load", 10.0)]),
+            benchmark("c", &[("load", 20.0)]),
+        ]);
+        assert!(!scores.contains_key("a"));
+        assert_close(scores["b"], 1.0);
+        assert_close(scores["c"], 2.0);
+    }
+
+    #[test]
+    fn ignores_excluded_runs() {
+        let mut warm_up = benchmark("a", &[("load", 100.0)]);
+        warm_up.excluded = Some(Exclusion::WarmUp);
+        let scores = scores(&[
+            warm_up,
+            benchmark("a", &[("load", 10.0)]),
+            benchmark("b", &[("load", 20.0)]),
+        ]);
+        assert_close(scores["a"], 1.0);
+        assert_close(scores["b"], 2.0);
+    }
+
+    #[test]
+    fn steps_only_some_frameworks_run_are_compared_among_those() {
+        let scores = scores(&[
+            benchmark("a", &[("load", 10.0), ("await todo focus", 5.0)]),
+            benchmark("b", &[("load", 10.0)]),
+        ]);
+        assert_close(scores["a"], 1.0);
+        assert_close(scores["b"], 1.0);
+    }
+}
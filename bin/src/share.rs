@@ -0,0 +1,181 @@
+//! Shares runs through links, with the benchmarks compressed into the url's
+//! fragment.
+use mogwai::{prelude::*, utils};
+
+use super::{bench_runner::Benchmark, graph::GraphMode, schema, show_comparison, show_graph};
+
+/// What the fragment of a shared link starts with, after the '#'.
+const FRAGMENT_PREFIX: &str = "results=";
+
+/// Past this many characters a link may not survive being pasted into an
+/// issue, so the per operation samples are left out.
+const MAX_PAYLOAD_LEN: usize = 8 * 1024;
+
+/// Decompressing more than this is not a shared run.
+const MAX_JSON_LEN: usize = 64 * 1024 * 1024;
+
+fn compress(benchmarks: &Vec<Benchmark>) -> Result<String, String> {
+    let json = serde_json::to_vec(&schema::envelope(benchmarks)).map_err(|e| e.to_string())?;
+    let compressed = miniz_oxide::deflate::compress_to_vec(&json, 9);
+    Ok(base64::encode_config(&compressed, base64::URL_SAFE_NO_PAD))
+}
+
+/// The benchmarks without their per operation samples.
+fn summarize(benchmarks: &Vec<Benchmark>) -> Vec<Benchmark> {
+    let mut summary = benchmarks.clone();
+    for step in summary
+        .iter_mut()
+        .flat_map(|benchmark| benchmark.steps.iter_mut())
+    {
+        step.cycles = None;
+    }
+    summary
+}
+
+/// Compress benchmarks into a url-safe payload, summarizing them if they
+/// don't fit in a link. It's an error if there are none, or if they don't fit
+/// even summarized.
+pub fn encode(benchmarks: &Vec<Benchmark>) -> Result<String, String> {
+    if benchmarks.is_empty() {
+        return Err("there is no run to share, run the bench or open a run first".to_string());
+    }
+    let payload = compress(benchmarks)?;
+    if payload.len() <= MAX_PAYLOAD_LEN {
+        return Ok(payload);
+    }
+    let payload = compress(&summarize(benchmarks))?;
+    if payload.len() <= MAX_PAYLOAD_LEN {
+        Ok(payload)
+    } else {
+        Err(format!(
+            "the run is too big to share as a link ({} characters, at most {}), \
+             export it instead",
+            payload.len(),
+            MAX_PAYLOAD_LEN
+        ))
+    }
+}
+
+pub fn decode(payload: &str) -> Result<Vec<Benchmark>, String> {
+    let compressed = base64::decode_config(payload, base64::URL_SAFE_NO_PAD)
+        .map_err(|e| format!("not base64url: {}", e))?;
+    let json = miniz_oxide::inflate::decompress_to_vec_with_limit(&compressed, MAX_JSON_LEN)
+        .map_err(|e| format!("could not decompress: {:?}", e))?;
+    let json = String::from_utf8(json).map_err(|e| format!("not text: {}", e))?;
+    schema::parse(&json)
+}
+
+/// A link to the page showing the given benchmarks.
+pub fn link(benchmarks: &Vec<Benchmark>) -> Result<String, String> {
+    let location = utils::window().location();
+    let page = location
+        .href()
+        .map_err(|e| format!("{:?}", e))?
+        .split('#')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    Ok(format!(
+        "{}#{}{}",
+        page,
+        FRAGMENT_PREFIX,
+        encode(benchmarks)?
+    ))
+}
+
+/// The benchmarks shared in the page's url, if it is a shared link.
+pub fn from_location() -> Option<Result<Vec<Benchmark>, String>> {
+    let hash = utils::window().location().hash().ok()?;
+    let payload = hash.trim_start_matches('#').strip_prefix(FRAGMENT_PREFIX)?;
+    Some(decode(payload).map_err(|e| format!("could not read the shared results: {}", e)))
+}
+
+/// A read-only page showing shared benchmarks.
+pub fn shared_component(benchmarks: Vec<Benchmark>) -> Component<Dom> {
+    let (tx_graph, rx_graph) = mpmc::bounded(1);
+    let (tx_compare, rx_compare) = mpmc::bounded(1);
+    let location = utils::window().location();
+    let bench_url = format!(
+        "{}{}",
+        location.pathname().unwrap_or_default(),
+        location.search().unwrap_or_default()
+    );
+    let view = builder! {
+        <div id="main" class="container-fluid">
+            <nav class="navbar navbar-light bg-light rounded-sm mt-2 mb-4">
+                <a href="https://github.com/schell/todo-mvc-bench">"schell's todo-mvc-bench"</a>
+                <span class="navbar-text mr-auto ml-2">"shared results"</span>
+                <a class="btn btn-primary" href=bench_url>"Run the bench"</a>
+            </nav>
+            <div class="container">
+                <div class="row embed-responsive embed-responsive-16by9 mb-4"
+                    post:build = move |dom: &mut Dom| tx_graph.try_send(dom.clone()).unwrap()>
+                </div>
+                <div post:build = move |dom: &mut Dom| tx_compare.try_send(dom.clone()).unwrap()>
+                </div>
+            </div>
+        </div>
+    };
+    Component::from(view).with_logic(async move {
+        let graph_dom = rx_graph.recv().await.unwrap();
+        let compare_dom = rx_compare.recv().await.unwrap();
+        show_graph(&graph_dom, &benchmarks, GraphMode::default());
+        show_comparison(&compare_dom, &benchmarks);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn benchmarks(cycles: usize) -> Vec<Benchmark> {
+        let mut benchmark = Benchmark::sequential("mogwai 0.5", &[("create todos", 100.0)]);
+        benchmark.steps[0].cycles = Some((0..cycles).map(|i| 1.0 + i as f64 / 7.0).collect());
+        vec![benchmark]
+    }
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        let decoded = decode(&encode(&benchmarks(10)).unwrap()).unwrap();
+        assert_eq!(decoded[0].name, "mogwai 0.5");
+        assert_eq!(decoded[0].steps[0].cycles.as_ref().unwrap().len(), 10);
+    }
+
+    #[test]
+    fn summarizes_what_does_not_fit_in_a_link() {
+        let payload = encode(&benchmarks(10_000)).unwrap();
+        assert!(payload.len() <= MAX_PAYLOAD_LEN);
+        let decoded = decode(&payload).unwrap();
+        assert_eq!(decoded[0].steps[0].cycles, None);
+        assert_eq!(decoded[0].steps[0].end, Some(100.0));
+    }
+
+    #[test]
+    fn refuses_an_empty_run() {
+        assert!(encode(&vec![]).is_err());
+    }
+
+    #[test]
+    fn refuses_what_does_not_fit_even_summarized() {
+        let many = (0..2000)
+            .map(|i| {
+                let mut benchmark = benchmarks(0).remove(0);
+                benchmark.name = format!("framework {}", i * 7919 % 100_003);
+                benchmark.steps[0].end = Some(i as f64 * 1.618);
+                benchmark
+            })
+            .collect::<Vec<_>>();
+        let err = encode(&many).unwrap_err();
+        assert!(err.starts_with("the run is too big to share"), "{}", err);
+    }
+
+    #[test]
+    fn reports_bad_payloads() {
+        assert!(decode("not base64!")
+            .unwrap_err()
+            .starts_with("not base64url"));
+        assert!(decode("AAAA")
+            .unwrap_err()
+            .starts_with("could not decompress"));
+    }
+}
@@ -1,38 +1,245 @@
+//! A history of benchmark runs in IndexedDB, or local storage where
+//! IndexedDB isn't available.
+//!
+//! The index of runs lives under `INDEX_KEY` and each run's benchmarks live
+//! under their own key, so listing the history doesn't parse every run. So
+//! does a summary of each run's times, so charting it doesn't either.
+//! Benchmarks are stored in a versioned envelope, see the `schema` module.
 use mogwai::utils;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json;
 use wasm_bindgen::JsValue;
-use web_sys::Storage;
 
-use super::bench_runner::Benchmark;
+use super::{
+    backend::{Backend, IndexedDb, LocalStorage},
+    bench_runner::{BenchConfig, Benchmark},
+    schema,
+    trend::{self, FrameworkTimes},
+};
 
-const KEY: &str = "todo-mvc-bench";
+/// Where the last run was kept before there was a history.
+const LEGACY_KEY: &str = "todo-mvc-bench";
+const INDEX_KEY: &str = "todo-mvc-bench-runs";
 
-pub fn write_items(items: &Vec<Benchmark>) -> Result<(), JsValue> {
-    let str_value = serde_json::to_string(items).expect("Could not serialize benchmarks");
-    utils::window()
-        .local_storage()?
-        .into_iter()
-        .for_each(|storage: Storage| {
-            storage
-                .set_item(KEY, &str_value)
-                .expect("could not store serialized bencmarks");
-        });
-    Ok(())
+fn run_key(id: &str) -> String {
+    format!("todo-mvc-bench-run/{}", id)
+}
+
+fn times_key(id: &str) -> String {
+    format!("todo-mvc-bench-run-times/{}", id)
 }
 
-pub fn read_benchmarks() -> Result<Vec<Benchmark>, JsValue> {
-    let storage = utils::window()
-        .local_storage()?
-        .expect("Could not get local storage");
+/// Everything about a run but its benchmarks.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunInfo {
+    pub id: String,
+    pub name: String,
+    /// Milliseconds since the unix epoch.
+    pub timestamp: f64,
+    pub user_agent: String,
+    /// `None` for runs made before the config was recorded.
+    pub config: Option<BenchConfig>,
+    /// The version of todo-mvc-bench that made the run.
+    pub crate_version: String,
+}
 
-    let may_item_str: Option<String> = storage.get_item(KEY).expect("Error using storage get_item");
+impl RunInfo {
+    /// Info for a run made just now, in this browser.
+    pub fn new(config: Option<BenchConfig>) -> Self {
+        let timestamp = js_sys::Date::now();
+        let id = format!("{:x}", timestamp as u64);
+        RunInfo {
+            name: format!("run {}", id),
+            id,
+            timestamp,
+            user_agent: utils::window().navigator().user_agent().unwrap_or_default(),
+            config,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
 
-    let items = may_item_str
-        .map(|json_str: String| {
-            let items: Vec<Benchmark> = serde_json::from_str(&json_str).unwrap_or(vec![]);
-            items
+    /// The run's timestamp in the browser's locale.
+    pub fn date_string(&self) -> String {
+        js_sys::Date::new(&JsValue::from_f64(self.timestamp))
+            .to_locale_string("default", &JsValue::UNDEFINED)
+            .into()
+    }
+}
+
+async fn read<T: DeserializeOwned>(backend: &dyn Backend, key: &str) -> Result<Option<T>, JsValue> {
+    backend
+        .get(key)
+        .await?
+        .map(|json_str| {
+            serde_json::from_str(&json_str)
+                .map_err(|e| JsValue::from_str(&format!("could not read '{}': {}", key, e)))
         })
-        .unwrap_or(vec![]);
+        .transpose()
+}
+
+async fn write<T: Serialize>(backend: &dyn Backend, key: &str, value: &T) -> Result<(), JsValue> {
+    let str_value = serde_json::to_string(value).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    backend.set(key, &str_value).await
+}
+
+/// Read the benchmarks stored under `key`, migrating them from older
+/// versions. Benchmarks that can't be read are an error, and left as they are.
+async fn read_benchmarks(
+    backend: &dyn Backend,
+    key: &str,
+) -> Result<Option<Vec<Benchmark>>, JsValue> {
+    backend
+        .get(key)
+        .await?
+        .map(|json_str| {
+            schema::parse(&json_str)
+                .map_err(|e| JsValue::from_str(&format!("could not read '{}': {}", key, e)))
+        })
+        .transpose()
+}
+
+pub struct Store {
+    backend: Box<dyn Backend>,
+    is_local_storage: bool,
+}
+
+impl Store {
+    /// Open IndexedDB, falling back to local storage.
+    pub async fn open() -> Self {
+        match IndexedDb::open().await {
+            Ok(db) => Store {
+                backend: Box::new(db),
+                is_local_storage: false,
+            },
+            Err(err) => {
+                log::warn!(
+                    "storing runs in local storage, IndexedDB is not available: {:?}",
+                    err
+                );
+                Store {
+                    backend: Box::new(LocalStorage),
+                    is_local_storage: true,
+                }
+            }
+        }
+    }
+
+    /// All stored runs, newest first.
+    pub async fn list_runs(&self) -> Result<Vec<RunInfo>, JsValue> {
+        Ok(read(self.backend.as_ref(), INDEX_KEY)
+            .await?
+            .unwrap_or_default())
+    }
+
+    async fn write_index(&self, runs: &Vec<RunInfo>) -> Result<(), JsValue> {
+        write(self.backend.as_ref(), INDEX_KEY, runs).await
+    }
+
+    pub async fn read_run(&self, id: &str) -> Result<Vec<Benchmark>, JsValue> {
+        read_benchmarks(self.backend.as_ref(), &run_key(id))
+            .await?
+            .ok_or_else(|| JsValue::from_str(&format!("there is no run {}", id)))
+    }
+
+    /// The summary of a run's times. Runs stored before there were summaries
+    /// are read once to make theirs.
+    pub async fn read_run_times(&self, id: &str) -> Result<Vec<FrameworkTimes>, JsValue> {
+        if let Some(times) = read(self.backend.as_ref(), &times_key(id)).await? {
+            return Ok(times);
+        }
+        let times = trend::run_times(&self.read_run(id).await?);
+        write(self.backend.as_ref(), &times_key(id), &times).await?;
+        Ok(times)
+    }
+
+    /// Store a new run at the front of the history.
+    pub async fn write_run(
+        &self,
+        info: &RunInfo,
+        benchmarks: &Vec<Benchmark>,
+    ) -> Result<(), JsValue> {
+        write(
+            self.backend.as_ref(),
+            &run_key(&info.id),
+            &schema::envelope(benchmarks),
+        )
+        .await?;
+        write(
+            self.backend.as_ref(),
+            &times_key(&info.id),
+            &trend::run_times(benchmarks),
+        )
+        .await?;
+        let mut runs = self.list_runs().await?;
+        runs.retain(|run| run.id != info.id);
+        runs.insert(0, info.clone());
+        self.write_index(&runs).await
+    }
+
+    pub async fn rename_run(&self, id: &str, name: &str) -> Result<(), JsValue> {
+        let mut runs = self.list_runs().await?;
+        for run in runs.iter_mut().filter(|run| run.id == id) {
+            run.name = name.to_string();
+        }
+        self.write_index(&runs).await
+    }
+
+    pub async fn delete_run(&self, id: &str) -> Result<(), JsValue> {
+        let mut runs = self.list_runs().await?;
+        runs.retain(|run| run.id != id);
+        self.write_index(&runs).await?;
+        self.backend.remove(&times_key(id)).await?;
+        self.backend.remove(&run_key(id)).await
+    }
+
+    /// Move runs kept in local storage into IndexedDB, merging the histories.
+    async fn migrate_local_storage(&self) -> Result<(), JsValue> {
+        let local_runs: Vec<RunInfo> = match read(&LocalStorage, INDEX_KEY).await? {
+            Some(runs) => runs,
+            None => return Ok(()),
+        };
+        for run in local_runs.iter() {
+            if let Some(benchmarks) = LocalStorage.get(&run_key(&run.id)).await? {
+                self.backend.set(&run_key(&run.id), &benchmarks).await?;
+            }
+            if let Some(times) = LocalStorage.get(&times_key(&run.id)).await? {
+                self.backend.set(&times_key(&run.id), &times).await?;
+            }
+        }
+        let mut runs = self.list_runs().await?;
+        runs.retain(|run| !local_runs.iter().any(|local_run| local_run.id == run.id));
+        runs.extend(local_runs.iter().cloned());
+        runs.sort_by(|a, b| {
+            b.timestamp
+                .partial_cmp(&a.timestamp)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        self.write_index(&runs).await?;
+
+        for run in local_runs.iter() {
+            LocalStorage.remove(&run_key(&run.id)).await?;
+            LocalStorage.remove(&times_key(&run.id)).await?;
+        }
+        LocalStorage.remove(INDEX_KEY).await
+    }
+
+    /// Move runs stored by older versions into the history. Data that can't
+    /// be read is left where it is.
+    pub async fn migrate(&self) -> Result<(), JsValue> {
+        if !self.is_local_storage {
+            self.migrate_local_storage().await?;
+        }
 
-    Ok(items)
+        let benchmarks = match read_benchmarks(&LocalStorage, LEGACY_KEY).await? {
+            Some(benchmarks) => benchmarks,
+            None => return Ok(()),
+        };
+        let mut info = RunInfo::new(None);
+        info.name = "migrated run".into();
+        // We don't know where or with what the legacy run was made.
+        info.user_agent = String::new();
+        info.crate_version = String::new();
+        self.write_run(&info, &benchmarks).await?;
+        LocalStorage.remove(LEGACY_KEY).await
+    }
 }
@@ -0,0 +1,422 @@
+//! A line chart of each framework's times across the stored runs, oldest
+//! first.
+use mogwai::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    bench_runner::Benchmark,
+    graph::{palette_color, SVGNS},
+    stats::Summary,
+    store::RunInfo,
+    In,
+};
+
+const WIDTH: f64 = 960.0;
+const MIN_HEIGHT: f64 = 320.0;
+const FONT_SIZE: f64 = 12.0;
+const LEGEND_LINE_HEIGHT: f64 = FONT_SIZE + 6.0;
+/// Room for the y axis labels on the left and the legend on the right.
+const PLOT_LEFT: f64 = 64.0;
+const PLOT_RIGHT: f64 = WIDTH - 200.0;
+const PLOT_TOP: f64 = 10.0;
+/// Room for the run numbers below the plot.
+const PLOT_MARGIN_BOTTOM: f64 = 30.0;
+
+/// Where the legend entry of the series at `index` sits.
+fn legend_y(index: usize) -> f64 {
+    PLOT_TOP + FONT_SIZE + index as f64 * LEGEND_LINE_HEIGHT
+}
+
+/// The height of a chart of `series` lines, tall enough for each to have a
+/// legend entry.
+fn chart_height(series: usize) -> f64 {
+    f64::max(MIN_HEIGHT, legend_y(series))
+}
+
+/// What a run is charted by: the summary of one framework's totals and of
+/// each of its steps. Kept apart from the run, so charting the history doesn't
+/// read every run's benchmarks.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FrameworkTimes {
+    pub name: String,
+    pub total: Option<Summary>,
+    /// In the order the steps ran.
+    pub steps: Vec<(String, Summary)>,
+}
+
+impl FrameworkTimes {
+    /// The summary of `step`, or of the totals when it's `None`.
+    fn summary(&self, step: Option<&str>) -> Option<Summary> {
+        match step {
+            Some(step) => self
+                .steps
+                .iter()
+                .find(|(name, _)| name == step)
+                .map(|(_, summary)| *summary),
+            None => self.total,
+        }
+    }
+}
+
+/// The times of each framework with samples in the run, in the order they
+/// first appear.
+pub fn run_times(benchmarks: &[Benchmark]) -> Vec<FrameworkTimes> {
+    let mut times: Vec<FrameworkTimes> = vec![];
+    let samples = benchmarks.iter().filter(|benchmark| benchmark.is_sample());
+    for benchmark in samples.clone() {
+        if times
+            .iter()
+            .any(|framework| framework.name == benchmark.name)
+        {
+            continue;
+        }
+        let runs = samples
+            .clone()
+            .filter(|other| other.name == benchmark.name)
+            .collect::<Vec<_>>();
+        let totals = runs
+            .iter()
+            .filter_map(|benchmark| benchmark.total())
+            .collect::<Vec<_>>();
+        let mut steps: Vec<(String, Summary)> = vec![];
+        for step in runs.iter().flat_map(|benchmark| benchmark.steps.iter()) {
+            if steps.iter().any(|(name, _)| *name == step.name) {
+                continue;
+            }
+            let durations = runs
+                .iter()
+                .flat_map(|benchmark| benchmark.steps.iter())
+                .filter(|other| other.name == step.name)
+                .filter_map(|other| other.duration())
+                .collect::<Vec<_>>();
+            if let Some(summary) = Summary::from_samples(&durations) {
+                steps.push((step.name.clone(), summary));
+            }
+        }
+        times.push(FrameworkTimes {
+            name: benchmark.name.clone(),
+            total: Summary::from_samples(&totals),
+            steps,
+        });
+    }
+    times
+}
+
+/// One framework's line.
+struct Series {
+    name: String,
+    /// The summary of each run, `None` where the framework has no samples.
+    points: Vec<Option<Summary>>,
+}
+
+/// A series for each framework in any of the runs, in the order they first
+/// appear.
+fn trend_series(runs: &[&[FrameworkTimes]], step: Option<&str>) -> Vec<Series> {
+    let mut names: Vec<&str> = vec![];
+    for framework in runs.iter().flat_map(|times| times.iter()) {
+        if !names.contains(&framework.name.as_str()) {
+            names.push(&framework.name);
+        }
+    }
+    names
+        .into_iter()
+        .map(|name| Series {
+            name: name.to_string(),
+            points: runs
+                .iter()
+                .map(|times| {
+                    times
+                        .iter()
+                        .find(|framework| framework.name == name)
+                        .and_then(|framework| framework.summary(step))
+                })
+                .collect(),
+        })
+        .filter(|series| series.points.iter().any(Option::is_some))
+        .collect()
+}
+
+/// The name of every step in the runs, in the order they first appear.
+fn step_names(runs: &[&[FrameworkTimes]]) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    let steps = runs
+        .iter()
+        .flat_map(|times| times.iter())
+        .flat_map(|framework| framework.steps.iter());
+    for (step, _) in steps {
+        if !names.contains(step) {
+            names.push(step.clone());
+        }
+    }
+    names
+}
+
+/// A round number of milliseconds to draw grid lines at.
+fn grid_step(max: f64) -> f64 {
+    let rough = max / 4.0;
+    let magnitude = 10f64.powf(rough.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * magnitude)
+        .find(|step| *step >= rough)
+        .unwrap_or(rough)
+}
+
+fn chart(runs: &[(RunInfo, Vec<FrameworkTimes>)], series: &[Series]) -> ViewBuilder<Dom> {
+    let max = series
+        .iter()
+        .flat_map(|series| series.points.iter().flatten())
+        .fold(0.0, |max, summary| f64::max(max, summary.median));
+    let height = chart_height(series.len());
+    let plot_bottom = height - PLOT_MARGIN_BOTTOM;
+    let grid = grid_step(max.max(1.0));
+    let top = (max / grid).ceil().max(1.0) * grid;
+    let to_x = |i: usize| {
+        if runs.len() > 1 {
+            PLOT_LEFT + i as f64 * (PLOT_RIGHT - PLOT_LEFT) / (runs.len() - 1) as f64
+        } else {
+            (PLOT_LEFT + PLOT_RIGHT) / 2.0
+        }
+    };
+    let to_y = |ms: f64| plot_bottom - ms / top * (plot_bottom - PLOT_TOP);
+
+    let mut tags = vec![];
+    for line in 0..=(top / grid).round() as usize {
+        let ms = line as f64 * grid;
+        let y = to_y(ms);
+        tags.push(builder! {
+            <line xmlns=SVGNS
+             x1=format!("{}", PLOT_LEFT)
+             x2=format!("{}", PLOT_RIGHT)
+             y1=format!("{}", y)
+             y2=format!("{}", y)
+             stroke="lightgrey"
+             stroke_width="1">
+            </line>
+        });
+        tags.push(builder! {
+            <text xmlns=SVGNS font_family="monospace" font_size="12" text_anchor="end"
+             x=format!("{}", PLOT_LEFT - 4.0)
+             y=format!("{}", y + FONT_SIZE / 3.0)>
+                {format!("{}ms", ms)}
+            </text>
+        });
+    }
+    for (i, (info, _)) in runs.iter().enumerate() {
+        tags.push(builder! {
+            <text xmlns=SVGNS font_family="monospace" font_size="12" text_anchor="middle"
+             x=format!("{}", to_x(i))
+             y=format!("{}", plot_bottom + FONT_SIZE + 6.0)>
+                <title xmlns=SVGNS>{format!("{}\n{}", info.name, info.date_string())}</title>
+                {format!("{}", i + 1)}
+            </text>
+        });
+    }
+
+    for (index, series) in series.iter().enumerate() {
+        let color = palette_color(index);
+        // Break the line where the framework wasn't run
+        let mut segments: Vec<Vec<(f64, f64)>> = vec![vec![]];
+        for (i, point) in series.points.iter().enumerate() {
+            match point {
+                Some(summary) => segments
+                    .last_mut()
+                    .unwrap()
+                    .push((to_x(i), to_y(summary.median))),
+                None => segments.push(vec![]),
+            }
+        }
+        for segment in segments.into_iter().filter(|s| s.len() > 1) {
+            let points = segment
+                .iter()
+                .map(|(x, y)| format!("{},{}", x, y))
+                .collect::<Vec<_>>()
+                .join(" ");
+            tags.push(builder! {
+                <polyline xmlns=SVGNS
+                 points=points
+                 fill="none"
+                 stroke=color
+                 stroke_width="2">
+                </polyline>
+            });
+        }
+        for (i, point) in series.points.iter().enumerate() {
+            let summary = match point {
+                Some(summary) => summary,
+                None => continue,
+            };
+            let (info, _) = &runs[i];
+            tags.push(builder! {
+                <circle xmlns=SVGNS
+                 cx=format!("{}", to_x(i))
+                 cy=format!("{}", to_y(summary.median))
+                 r="4"
+                 fill=color
+                 style="cursor: pointer;">
+                    <title xmlns=SVGNS>
+                        {format!(
+                            "{} in {} ({})\n{}",
+                            series.name,
+                            info.name,
+                            info.date_string(),
+                            summary
+                        )}
+                    </title>
+                </circle>
+            });
+        }
+
+        let legend_y = legend_y(index);
+        tags.push(builder! {
+            <rect xmlns=SVGNS
+             x=format!("{}", PLOT_RIGHT + 16.0)
+             y=format!("{}", legend_y - FONT_SIZE + 2.0)
+             width="10"
+             height="10"
+             fill=color>
+            </rect>
+        });
+        tags.push(builder! {
+            <text xmlns=SVGNS font_family="monospace" font_size="12"
+             x=format!("{}", PLOT_RIGHT + 32.0)
+             y=format!("{}", legend_y)>
+                {&series.name}
+            </text>
+        });
+    }
+
+    builder! {
+        <svg xmlns=SVGNS
+         width=format!("{}", WIDTH)
+         height=format!("{}", height)
+         viewBox=format!("0 0 {} {}", WIDTH, height)
+         class="w-100">
+            {tags}
+        </svg>
+    }
+}
+
+/// The median time of each framework in each stored run, with a select to
+/// chart one step instead of the totals.
+pub fn trend_view(
+    runs: &[(RunInfo, Vec<FrameworkTimes>)],
+    step: Option<&str>,
+    tx: &broadcast::Sender<In>,
+) -> ViewBuilder<Dom> {
+    let times = runs
+        .iter()
+        .map(|(_, times)| times.as_slice())
+        .collect::<Vec<_>>();
+    let series = trend_series(&times, step);
+    let step_options = step_names(&times)
+        .into_iter()
+        .map(|name| {
+            let is_selected = Some(name.as_str()) == step;
+            builder! {
+                <option value=&name boolean:selected=is_selected>
+                    {&name}
+                </option>
+            }
+        })
+        .collect::<Vec<_>>();
+    let content = if series.is_empty() {
+        builder! {
+            <p class="text-muted">"There are no stored runs to chart."</p>
+        }
+    } else {
+        chart(runs, &series)
+    };
+    builder! {
+        <div class="w-100">
+            <div class="input-group input-group-sm col-3 mb-2 pl-0">
+                <div class="input-group-prepend">
+                    <span class="input-group-text">"time of"</span>
+                </div>
+                <select
+                 class="custom-select"
+                 on:change = tx.sink().contra_filter_map(|event: Event| {
+                     In::from_trend_step_change_event(event)
+                 })>
+                    <option value="" boolean:selected=step.is_none()>"total"</option>
+                    {step_options}
+                </select>
+            </div>
+            {content}
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn benchmark(name: &str, create: f64) -> Benchmark {
+        Benchmark::sequential(name, &[("create todos", create), ("delete todos", 1.0)])
+    }
+
+    #[test]
+    fn has_a_point_per_run_a_framework_was_in() {
+        let runs = [
+            run_times(&[benchmark("a", 10.0), benchmark("a", 20.0)]),
+            run_times(&[benchmark("a", 30.0), benchmark("b", 5.0)]),
+        ];
+        let runs = runs.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        let totals = trend_series(&runs, None);
+        assert_eq!(totals.len(), 2);
+        let a = totals[0]
+            .points
+            .iter()
+            .map(|p| p.map(|s| s.median))
+            .collect::<Vec<_>>();
+        assert_eq!(a, vec![Some(16.0), Some(31.0)]);
+        let b = totals[1]
+            .points
+            .iter()
+            .map(|p| p.map(|s| s.median))
+            .collect::<Vec<_>>();
+        assert_eq!(b, vec![None, Some(6.0)]);
+
+        let creates = trend_series(&runs, Some("create todos"));
+        assert_eq!(creates[0].points[0].unwrap().median, 15.0);
+        assert!(trend_series(&runs, Some("fly")).is_empty());
+    }
+
+    #[test]
+    fn times_leave_out_runs_that_are_not_samples() {
+        let mut failed = benchmark("a", 100.0);
+        failed.failed_message = Some("oops".into());
+        let times = run_times(&[benchmark("a", 10.0), failed, benchmark("a", 20.0)]);
+        assert_eq!(times.len(), 1);
+        assert_eq!(times[0].total.unwrap().n, 2);
+        let steps = times[0]
+            .steps
+            .iter()
+            .map(|(step, _)| step.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(steps, vec!["create todos", "delete todos"]);
+
+        let json = serde_json::to_string(&times).unwrap();
+        let read: Vec<FrameworkTimes> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            read[0].summary(Some("create todos")),
+            times[0].summary(Some("create todos"))
+        );
+    }
+
+    #[test]
+    fn grows_to_fit_the_legend() {
+        assert_eq!(chart_height(3), MIN_HEIGHT);
+        let frameworks = 40;
+        let height = chart_height(frameworks);
+        assert!(height > MIN_HEIGHT);
+        assert!(legend_y(frameworks - 1) < height);
+    }
+
+    #[test]
+    fn grid_lines_fall_on_round_numbers() {
+        assert_eq!(grid_step(1000.0), 500.0);
+        assert_eq!(grid_step(130.0), 50.0);
+        assert_eq!(grid_step(7.0), 2.0);
+    }
+}
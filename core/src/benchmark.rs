@@ -0,0 +1,277 @@
+//! The results of benchmarking a framework.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::stats::{OutlierFences, Percentiles};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BenchmarkStep {
+    pub name: String,
+    pub start: f64,
+    pub end: Option<f64>,
+    /// Milliseconds taken by each operation of a step that loops, like each
+    /// todo created by "create todos".
+    pub cycles: Option<Vec<f64>>,
+    /// When the DOM reached the state the step was waiting for.
+    #[serde(default)]
+    pub dom_ready: Option<f64>,
+    /// When the todo document painted that state, if the step waited for it.
+    #[serde(default)]
+    pub painted: Option<f64>,
+}
+
+impl BenchmarkStep {
+    pub fn new(name: &str, start: f64) -> Self {
+        BenchmarkStep {
+            name: name.to_string(),
+            start,
+            end: None,
+            cycles: None,
+            dom_ready: None,
+            painted: None,
+        }
+    }
+
+    /// A step that ran from `start` to `end`.
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn finished(name: &str, start: f64, end: f64) -> Self {
+        BenchmarkStep {
+            end: Some(end),
+            ..BenchmarkStep::new(name, start)
+        }
+    }
+
+    pub fn duration(&self) -> Option<f64> {
+        self.end.map(|end| end - self.start)
+    }
+
+    /// Percentiles of the step's per operation samples, if it has any.
+    pub fn cycle_percentiles(&self) -> Option<Percentiles> {
+        Percentiles::from_samples(self.cycles.as_ref()?)
+    }
+}
+
+/// Bytes downloaded for one kind of resource, as reported by the Resource
+/// Timing API.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ResourceSize {
+    /// Bytes over the wire including headers, zero when served from cache.
+    pub transfer: f64,
+    /// Body bytes before decompression.
+    pub encoded: f64,
+    /// Body bytes after decompression.
+    pub decoded: f64,
+}
+
+impl ResourceSize {
+    pub fn add(&mut self, other: &ResourceSize) {
+        self.transfer += other.transfer;
+        self.encoded += other.encoded;
+        self.decoded += other.decoded;
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ResourceSizes {
+    pub js: ResourceSize,
+    pub wasm: ResourceSize,
+    pub css: ResourceSize,
+}
+
+impl ResourceSizes {
+    pub fn total(&self) -> ResourceSize {
+        let mut total = ResourceSize::default();
+        total.add(&self.js);
+        total.add(&self.wasm);
+        total.add(&self.css);
+        total
+    }
+
+    /// The size a resource at `url` counts towards, if it's one that's
+    /// counted.
+    pub fn kind_mut(&mut self, url: &str, initiator_type: &str) -> Option<&mut ResourceSize> {
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        let path = path.to_lowercase();
+        if path.ends_with(".wasm") {
+            Some(&mut self.wasm)
+        } else if path.ends_with(".css") {
+            Some(&mut self.css)
+        } else if path.ends_with(".js") || path.ends_with(".mjs") || initiator_type == "script" {
+            Some(&mut self.js)
+        } else {
+            None
+        }
+    }
+}
+
+/// Why a benchmark is kept but left out of graphs, scores and summaries.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Exclusion {
+    /// A warm-up run, made before the runs that count.
+    WarmUp,
+    /// Its total time fell outside the outlier fences of its framework's runs.
+    Outlier,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Benchmark {
+    pub name: String,
+    pub steps: Vec<BenchmarkStep>,
+    pub failed_message: Option<String>,
+    pub language: Option<String>,
+    #[serde(default)]
+    pub sizes: Option<ResourceSizes>,
+    #[serde(default)]
+    pub excluded: Option<Exclusion>,
+}
+
+impl Benchmark {
+    pub fn new() -> Self {
+        Benchmark {
+            name: "unnamed".into(),
+            steps: vec![],
+            failed_message: None,
+            language: None,
+            sizes: None,
+            excluded: None,
+        }
+    }
+
+    /// A benchmark of `name` whose steps ran back to back from zero, each for
+    /// the given milliseconds.
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn sequential(name: &str, steps: &[(&str, f64)]) -> Self {
+        let mut start = 0.0;
+        let steps = steps
+            .iter()
+            .map(|(step, duration)| {
+                let step = BenchmarkStep::finished(step, start, start + duration);
+                start += duration;
+                step
+            })
+            .collect();
+        Benchmark {
+            name: name.into(),
+            steps,
+            ..Benchmark::new()
+        }
+    }
+
+    /// Whether this benchmark succeeded and wasn't excluded, so it should be
+    /// aggregated.
+    pub fn is_sample(&self) -> bool {
+        self.failed_message.is_none() && self.excluded.is_none()
+    }
+
+    pub fn total(&self) -> Option<f64> {
+        self.steps.iter().map(|step| step.duration()).sum()
+    }
+}
+
+impl Default for Benchmark {
+    fn default() -> Self {
+        Benchmark::new()
+    }
+}
+
+/// Mark the samples of each framework whose total time falls outside the
+/// given fences as outliers.
+pub fn exclude_outliers(benchmarks: &mut [Benchmark], fences: OutlierFences) {
+    let mut totals: HashMap<String, Vec<f64>> = HashMap::new();
+    for benchmark in benchmarks.iter().filter(|b| b.is_sample()) {
+        totals
+            .entry(benchmark.name.clone())
+            .or_default()
+            .extend(benchmark.total());
+    }
+    let bounds = totals
+        .into_iter()
+        .filter_map(|(name, totals)| Some((name, fences.bounds(&totals)?)))
+        .collect::<HashMap<_, _>>();
+    for benchmark in benchmarks.iter_mut().filter(|b| b.is_sample()) {
+        let (low, high) = match bounds.get(&benchmark.name) {
+            Some(bounds) => *bounds,
+            None => continue,
+        };
+        if let Some(total) = benchmark.total() {
+            if total < low || total > high {
+                benchmark.excluded = Some(Exclusion::Outlier);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn benchmark(name: &str, total: f64) -> Benchmark {
+        Benchmark::sequential(name, &[("create todos", total)])
+    }
+
+    fn outliers(benchmarks: &[Benchmark]) -> Vec<usize> {
+        benchmarks
+            .iter()
+            .enumerate()
+            .filter(|(_, benchmark)| benchmark.excluded == Some(Exclusion::Outlier))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    #[test]
+    fn fences_each_framework_on_its_own() {
+        let mut benchmarks = vec![
+            benchmark("a", 10.0),
+            benchmark("a", 11.0),
+            benchmark("a", 12.0),
+            benchmark("a", 13.0),
+            benchmark("a", 40.0),
+            // 40 is in range for b, which is slower
+            benchmark("b", 38.0),
+            benchmark("b", 39.0),
+            benchmark("b", 40.0),
+            benchmark("b", 41.0),
+        ];
+        exclude_outliers(&mut benchmarks, OutlierFences::Inner);
+        assert_eq!(outliers(&benchmarks), vec![4]);
+    }
+
+    #[test]
+    fn only_marks_samples() {
+        let mut failed = benchmark("a", 1000.0);
+        failed.failed_message = Some("timed out".into());
+        let mut warm_up = benchmark("a", 1000.0);
+        warm_up.excluded = Some(Exclusion::WarmUp);
+        let mut benchmarks = vec![
+            failed,
+            warm_up,
+            benchmark("a", 10.0),
+            benchmark("a", 11.0),
+            benchmark("a", 12.0),
+            benchmark("a", 13.0),
+        ];
+        exclude_outliers(&mut benchmarks, OutlierFences::Inner);
+        assert!(outliers(&benchmarks).is_empty());
+        assert_eq!(benchmarks[0].excluded, None);
+        assert_eq!(benchmarks[1].excluded, Some(Exclusion::WarmUp));
+    }
+
+    #[test]
+    fn keeps_everything_when_off_or_too_few() {
+        let totals = [10.0, 11.0, 12.0, 13.0, 40.0];
+        let mut benchmarks = totals
+            .iter()
+            .map(|t| benchmark("a", *t))
+            .collect::<Vec<_>>();
+        exclude_outliers(&mut benchmarks, OutlierFences::Off);
+        assert!(outliers(&benchmarks).is_empty());
+
+        let mut benchmarks = totals[2..]
+            .iter()
+            .map(|t| benchmark("a", *t))
+            .collect::<Vec<_>>();
+        exclude_outliers(&mut benchmarks, OutlierFences::Inner);
+        assert!(outliers(&benchmarks).is_empty());
+    }
+}
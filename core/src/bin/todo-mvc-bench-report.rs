@@ -0,0 +1,212 @@
+//! Reports on benchmarks exported from todo-mvc-bench as JSON.
+//!
+//! ```text
+//! todo-mvc-bench-report [--format markdown|csv|json] [--baseline FILE]
+//!                       [--threshold PERCENT] [--fail-on-regression] FILE...
+//! ```
+//!
+//! Prints each file's per framework statistics, then how each file differs
+//! from the one before it, or from the baseline if one is given.
+use std::{fs, process};
+
+use todo_mvc_bench_core::{
+    benchmark::Benchmark,
+    export,
+    report::{Report, DEFAULT_THRESHOLD},
+};
+
+const USAGE: &str = "usage: todo-mvc-bench-report [--format markdown|csv|json] [--baseline FILE] \
+                     [--threshold PERCENT] [--fail-on-regression] FILE...";
+
+#[derive(Debug, PartialEq)]
+enum Format {
+    Markdown,
+    Csv,
+    Json,
+}
+
+struct Args {
+    format: Format,
+    baseline: Option<String>,
+    /// A fraction, not a percent.
+    threshold: f64,
+    fail_on_regression: bool,
+    files: Vec<String>,
+}
+
+fn parse_format(name: &str) -> Result<Format, String> {
+    match name {
+        "markdown" | "md" => Ok(Format::Markdown),
+        "csv" => Ok(Format::Csv),
+        "json" => Ok(Format::Json),
+        _ => Err(format!("unknown format '{}'", name)),
+    }
+}
+
+fn parse_threshold(percent: &str) -> Result<f64, String> {
+    match percent.trim_end_matches('%').parse::<f64>() {
+        Ok(percent) if percent >= 0.0 => Ok(percent / 100.0),
+        _ => Err(format!("'{}' is not a percent", percent)),
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args {
+        format: Format::Markdown,
+        baseline: None,
+        threshold: DEFAULT_THRESHOLD,
+        fail_on_regression: false,
+        files: vec![],
+    };
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            println!("{}", USAGE);
+            process::exit(0);
+        }
+        if arg == "--fail-on-regression" {
+            parsed.fail_on_regression = true;
+            continue;
+        }
+        if !arg.starts_with('-') {
+            parsed.files.push(arg);
+            continue;
+        }
+        let (option, value) = match arg.find('=') {
+            Some(i) => (arg[..i].to_string(), arg[i + 1..].to_string()),
+            None => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("{} needs a value", arg))?;
+                (arg, value)
+            }
+        };
+        match option.as_str() {
+            "--format" | "-f" => parsed.format = parse_format(&value)?,
+            "--baseline" | "-b" => parsed.baseline = Some(value),
+            "--threshold" | "-t" => parsed.threshold = parse_threshold(&value)?,
+            _ => return Err(format!("unknown option '{}'", option)),
+        }
+    }
+    if parsed.files.is_empty() {
+        return Err("no files given".into());
+    }
+    Ok(parsed)
+}
+
+fn read(file: String) -> Result<(String, Vec<Benchmark>), String> {
+    let json = fs::read_to_string(&file).map_err(|e| format!("{}: {}", file, e))?;
+    let benchmarks = export::from_json(&json).map_err(|e| format!("{}: {}", file, e))?;
+    Ok((file, benchmarks))
+}
+
+/// The report, and whether anything regressed.
+fn run() -> Result<(String, bool), String> {
+    let args = parse_args(std::env::args().skip(1))?;
+    let runs = args
+        .files
+        .into_iter()
+        .map(read)
+        .collect::<Result<Vec<_>, _>>()?;
+    let report = match args.baseline {
+        Some(baseline) => Report::against_baseline(read(baseline)?, &runs, args.threshold),
+        None => Report::new(&runs, args.threshold),
+    };
+    let regressed = args.fail_on_regression
+        && report
+            .diffs
+            .iter()
+            .flat_map(|diff| diff.steps.iter())
+            .any(|step| step.regressed);
+    let too_few_samples = report
+        .diffs
+        .iter()
+        .map(|diff| diff.too_few_samples())
+        .sum::<usize>();
+    if args.fail_on_regression && too_few_samples > 0 {
+        // These don't fail the check, but they aren't a pass either
+        eprintln!(
+            "{} steps are past the threshold but have too few samples to tell whether they \
+             regressed, compare runs of at least 4 iterations",
+            too_few_samples
+        );
+    }
+    let output = match args.format {
+        Format::Markdown => report.to_markdown(),
+        Format::Csv => report.to_csv(),
+        Format::Json => report.to_json()?,
+    };
+    Ok((output, regressed))
+}
+
+fn main() {
+    match run() {
+        Ok((output, regressed)) => {
+            print!("{}", output);
+            if regressed {
+                process::exit(2);
+            }
+        }
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn reads_options_with_or_without_an_equals() {
+        let args = parse(&[
+            "--format=csv",
+            "-b",
+            "main.json",
+            "--threshold",
+            "5",
+            "a.json",
+        ])
+        .unwrap();
+        assert_eq!(args.format, Format::Csv);
+        assert_eq!(args.baseline.as_deref(), Some("main.json"));
+        assert!((args.threshold - 0.05).abs() < 1e-9);
+        assert!(!args.fail_on_regression);
+        assert_eq!(args.files, vec!["a.json"]);
+
+        let args = parse(&["a.json", "-f", "json", "--baseline=main.json", "b.json"]).unwrap();
+        assert_eq!(args.format, Format::Json);
+        assert_eq!(args.baseline.as_deref(), Some("main.json"));
+        assert_eq!(args.files, vec!["a.json", "b.json"]);
+    }
+
+    #[test]
+    fn defaults_to_markdown_and_the_default_threshold() {
+        let args = parse(&["--fail-on-regression", "a.json"]).unwrap();
+        assert_eq!(args.format, Format::Markdown);
+        assert_eq!(args.threshold, DEFAULT_THRESHOLD);
+        assert!(args.fail_on_regression);
+    }
+
+    #[test]
+    fn reads_thresholds_as_percents() {
+        assert!((parse_threshold("10%").unwrap() - 0.1).abs() < 1e-9);
+        assert!((parse_threshold("2.5").unwrap() - 0.025).abs() < 1e-9);
+        assert!(parse_threshold("-1").is_err());
+        assert!(parse_threshold("ten").is_err());
+    }
+
+    #[test]
+    fn reports_bad_arguments() {
+        let err = |args: &[&str]| parse(args).err().unwrap();
+        assert_eq!(err(&["--fly", "x", "a.json"]), "unknown option '--fly'");
+        assert_eq!(err(&["a.json", "--format"]), "--format needs a value");
+        assert_eq!(err(&["-f=yaml", "a.json"]), "unknown format 'yaml'");
+        assert_eq!(err(&["-t", "ten", "a.json"]), "'ten' is not a percent");
+        assert_eq!(err(&["-f", "csv"]), "no files given");
+    }
+}
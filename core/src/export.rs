@@ -0,0 +1,202 @@
+//! Exports benchmarks as JSON or CSV, and imports exported JSON.
+use std::collections::HashMap;
+
+use crate::{
+    benchmark::{Benchmark, BenchmarkStep, Exclusion},
+    schema,
+    stats::Percentiles,
+};
+
+/// Benchmarks in the same versioned envelope they're stored in, so exports
+/// can be migrated when the format changes.
+pub fn to_json(benchmarks: &Vec<Benchmark>) -> Result<String, String> {
+    serde_json::to_string_pretty(&schema::envelope(benchmarks)).map_err(|e| e.to_string())
+}
+
+fn validate_step(step: &BenchmarkStep) -> Result<(), String> {
+    if step.name.trim().is_empty() {
+        return Err("name is empty".into());
+    }
+    if !step.start.is_finite() {
+        return Err("start is not a number".into());
+    }
+    if let Some(end) = step.end {
+        if !end.is_finite() || end < step.start {
+            return Err("ends before it starts".into());
+        }
+    }
+    if step
+        .cycles
+        .iter()
+        .flatten()
+        .any(|cycle| !cycle.is_finite() || *cycle < 0.0)
+    {
+        return Err("has an invalid operation time".into());
+    }
+    Ok(())
+}
+
+fn validate(benchmark: &Benchmark) -> Result<(), String> {
+    if benchmark.name.trim().is_empty() {
+        return Err("name is empty".into());
+    }
+    for (i, step) in benchmark.steps.iter().enumerate() {
+        validate_step(step).map_err(|e| format!("step {} ('{}'): {}", i, step.name, e))?;
+    }
+    Ok(())
+}
+
+/// Parse and validate benchmarks exported by `to_json`, from any version.
+pub fn from_json(json: &str) -> Result<Vec<Benchmark>, String> {
+    let benchmarks = schema::parse(json).map_err(|e| format!("not exported benchmarks: {}", e))?;
+    for (i, benchmark) in benchmarks.iter().enumerate() {
+        validate(benchmark)
+            .map_err(|e| format!("benchmark {} ('{}'): {}", i, benchmark.name, e))?;
+    }
+    Ok(benchmarks)
+}
+
+const CSV_HEADER: [&str; 13] = [
+    "framework",
+    "language",
+    "run",
+    "excluded",
+    "failed_message",
+    "step",
+    "start_ms",
+    "end_ms",
+    "duration_ms",
+    "operations",
+    "operation_p50_ms",
+    "operation_p95_ms",
+    "operation_p99_ms",
+];
+
+/// Quote a CSV field if it needs it.
+pub(crate) fn csv_field(field: String) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+pub(crate) fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+fn step_fields(step: Option<&BenchmarkStep>) -> Vec<String> {
+    let step = match step {
+        Some(step) => step,
+        None => return vec![String::new(); 8],
+    };
+    let cycles = step.cycles.as_ref();
+    let percentiles = cycles.and_then(|cycles| Percentiles::from_samples(cycles));
+    vec![
+        step.name.clone(),
+        step.start.to_string(),
+        optional(step.end),
+        optional(step.duration()),
+        optional(cycles.map(|cycles| cycles.len())),
+        optional(percentiles.map(|p| p.p50)),
+        optional(percentiles.map(|p| p.p95)),
+        optional(percentiles.map(|p| p.p99)),
+    ]
+}
+
+/// One row per framework, run and step. Runs are numbered from 1 for each
+/// framework, and a run that failed before its first step gets a single row
+/// without a step.
+pub fn to_csv(benchmarks: &[Benchmark]) -> String {
+    let mut runs: HashMap<&str, usize> = HashMap::new();
+    let mut lines = vec![CSV_HEADER.join(",")];
+    for benchmark in benchmarks.iter() {
+        let run = runs.entry(benchmark.name.as_str()).or_insert(0);
+        *run += 1;
+        let run_fields = [
+            benchmark.name.clone(),
+            optional(benchmark.language.as_ref()),
+            run.to_string(),
+            optional(benchmark.excluded.map(|exclusion| match exclusion {
+                Exclusion::WarmUp => "warm_up",
+                Exclusion::Outlier => "outlier",
+            })),
+            optional(benchmark.failed_message.as_ref()),
+        ];
+        let steps = if benchmark.steps.is_empty() {
+            vec![None]
+        } else {
+            benchmark.steps.iter().map(Some).collect()
+        };
+        for step in steps.into_iter() {
+            let line = run_fields
+                .iter()
+                .cloned()
+                .chain(step_fields(step))
+                .map(csv_field)
+                .collect::<Vec<_>>()
+                .join(",");
+            lines.push(line);
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imports_what_it_exports() {
+        let mut a = Benchmark::new();
+        a.name = "a".into();
+        a.steps = vec![BenchmarkStep::finished("load", 0.0, 10.0)];
+        a.steps[0].cycles = Some(vec![1.0, 2.0]);
+        let imported = from_json(&to_json(&vec![a]).unwrap()).unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].steps[0].cycles, Some(vec![1.0, 2.0]));
+    }
+
+    #[test]
+    fn rejects_invalid_benchmarks() {
+        assert!(from_json("{\"name\": \"a\"}").is_err());
+        assert!(from_json("[{\"name\": \"a\"}]").is_err());
+
+        let mut a = Benchmark::new();
+        a.name = "a".into();
+        a.steps = vec![
+            BenchmarkStep::finished("load", 0.0, 10.0),
+            BenchmarkStep::finished("create todos", 10.0, 5.0),
+        ];
+        let err = from_json(&to_json(&vec![a]).unwrap()).unwrap_err();
+        assert_eq!(
+            err,
+            "benchmark 0 ('a'): step 1 ('create todos'): ends before it starts"
+        );
+    }
+
+    #[test]
+    fn has_a_row_per_framework_run_and_step() {
+        let mut a = Benchmark::new();
+        a.name = "a".into();
+        a.steps = vec![
+            BenchmarkStep::finished("load", 0.0, 10.0),
+            BenchmarkStep::finished("create todos", 10.0, 30.0),
+        ];
+        a.steps[1].cycles = Some(vec![1.0, 2.0, 3.0]);
+        let mut failed = a.clone();
+        failed.steps.clear();
+        failed.failed_message = Some("could not find \"input\", giving up".into());
+
+        let csv = to_csv(&[a, failed]);
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], CSV_HEADER.join(","));
+        assert_eq!(lines[1], "a,,1,,,load,0,10,10,,,,");
+        assert_eq!(lines[2], "a,,1,,,create todos,10,30,20,3,2,2.9,2.98");
+        assert_eq!(
+            lines[3],
+            "a,,2,,\"could not find \"\"input\"\", giving up\",,,,,,,,"
+        );
+    }
+}
@@ -0,0 +1,8 @@
+//! Benchmark results and the analysis of them, shared by the web app and the
+//! `todo-mvc-bench-report` command line tool. Nothing in here depends on the
+//! browser.
+pub mod benchmark;
+pub mod export;
+pub mod report;
+pub mod schema;
+pub mod stats;
@@ -0,0 +1,610 @@
+//! Statistics of exported runs, and the differences between them, as
+//! markdown, CSV or JSON.
+//!
+//! Steps are matched between runs by framework and step name. A step whose
+//! median got slower by more than a threshold is a regression, if the change
+//! is also significant. Telling that takes a few samples of each run, so with
+//! fewer a slower step is reported as having too few samples instead.
+use serde::Serialize;
+
+use crate::{
+    benchmark::Benchmark,
+    export::{csv_field, optional},
+    stats::{self, Summary},
+};
+
+/// Steps this much slower than before are regressions, as a fraction of the
+/// time before.
+pub const DEFAULT_THRESHOLD: f64 = 0.1;
+
+/// A framework's samples in one run: the duration of each step, in the order
+/// the steps ran, then the totals.
+struct FrameworkSamples {
+    name: String,
+    language: Option<String>,
+    failed: usize,
+    excluded: usize,
+    steps: Vec<(String, Vec<f64>)>,
+}
+
+impl FrameworkSamples {
+    fn step(&self, name: &str) -> Option<&[f64]> {
+        self.steps
+            .iter()
+            .find(|(step, _)| step == name)
+            .map(|(_, samples)| samples.as_slice())
+    }
+}
+
+fn framework_samples(benchmarks: &[Benchmark]) -> Vec<FrameworkSamples> {
+    let mut frameworks: Vec<FrameworkSamples> = vec![];
+    for benchmark in benchmarks.iter() {
+        let index = match frameworks.iter().position(|f| f.name == benchmark.name) {
+            Some(index) => index,
+            None => {
+                frameworks.push(FrameworkSamples {
+                    name: benchmark.name.clone(),
+                    language: benchmark.language.clone(),
+                    failed: 0,
+                    excluded: 0,
+                    steps: vec![],
+                });
+                frameworks.len() - 1
+            }
+        };
+        let framework = &mut frameworks[index];
+        if benchmark.failed_message.is_some() {
+            framework.failed += 1;
+        } else if benchmark.excluded.is_some() {
+            framework.excluded += 1;
+        }
+        if !benchmark.is_sample() {
+            continue;
+        }
+        for step in benchmark.steps.iter() {
+            let duration = match step.duration() {
+                Some(duration) => duration,
+                None => continue,
+            };
+            match framework
+                .steps
+                .iter_mut()
+                .find(|(name, _)| *name == step.name)
+            {
+                Some((_, samples)) => samples.push(duration),
+                None => framework.steps.push((step.name.clone(), vec![duration])),
+            }
+        }
+    }
+    for framework in frameworks.iter_mut() {
+        let totals = benchmarks
+            .iter()
+            .filter(|b| b.name == framework.name && b.is_sample())
+            .filter_map(|b| b.total())
+            .collect::<Vec<_>>();
+        if !totals.is_empty() {
+            framework.steps.push(("total".into(), totals));
+        }
+    }
+    frameworks
+}
+
+#[derive(Debug, Serialize)]
+pub struct StepStats {
+    pub step: String,
+    pub summary: Summary,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FrameworkStats {
+    pub framework: String,
+    pub language: Option<String>,
+    pub failed: usize,
+    /// Warm-up and outlier runs.
+    pub excluded: usize,
+    /// Each step in the order they ran, then "total".
+    pub steps: Vec<StepStats>,
+}
+
+/// The statistics of one exported file.
+#[derive(Debug, Serialize)]
+pub struct RunStats {
+    pub run: String,
+    pub frameworks: Vec<FrameworkStats>,
+}
+
+/// How one framework's step changed between two runs.
+#[derive(Debug, Serialize)]
+pub struct StepDiff {
+    pub framework: String,
+    pub step: String,
+    pub before_median: f64,
+    pub after_median: f64,
+    /// `after_median / before_median`, above 1 when the step got slower.
+    pub ratio: f64,
+    /// The p-value of a Mann-Whitney U test of the two runs' samples.
+    pub p: Option<f64>,
+    pub significant: bool,
+    /// Whether `ratio` is past `1 + threshold` and the change is significant.
+    pub regressed: bool,
+    /// Whether `ratio` is past `1 + threshold`, but there are too few samples
+    /// for any change to be significant, so it can't be told if it regressed.
+    pub too_few_samples: bool,
+}
+
+/// The steps that both runs made, for frameworks in both runs.
+#[derive(Debug, Serialize)]
+pub struct RunDiff {
+    pub before: String,
+    pub after: String,
+    pub steps: Vec<StepDiff>,
+}
+
+impl RunDiff {
+    pub fn regressions(&self) -> usize {
+        self.steps.iter().filter(|step| step.regressed).count()
+    }
+
+    /// The steps past the threshold with too few samples to tell whether
+    /// they regressed.
+    pub fn too_few_samples(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|step| step.too_few_samples)
+            .count()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// The fraction a step has to slow down by to be a regression.
+    pub threshold: f64,
+    pub runs: Vec<RunStats>,
+    /// Each run after the first against the one before it, or against the
+    /// baseline.
+    pub diffs: Vec<RunDiff>,
+}
+
+fn run_stats(run: &str, frameworks: &[FrameworkSamples]) -> RunStats {
+    RunStats {
+        run: run.to_string(),
+        frameworks: frameworks
+            .iter()
+            .map(|framework| FrameworkStats {
+                framework: framework.name.clone(),
+                language: framework.language.clone(),
+                failed: framework.failed,
+                excluded: framework.excluded,
+                steps: framework
+                    .steps
+                    .iter()
+                    .filter_map(|(step, samples)| {
+                        Some(StepStats {
+                            step: step.clone(),
+                            summary: Summary::from_samples(samples)?,
+                        })
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+fn step_diffs(
+    before: &[FrameworkSamples],
+    after: &[FrameworkSamples],
+    threshold: f64,
+) -> Vec<StepDiff> {
+    let mut steps = vec![];
+    for framework in after.iter() {
+        let framework_before = match before.iter().find(|f| f.name == framework.name) {
+            Some(framework_before) => framework_before,
+            None => continue,
+        };
+        for (step, samples) in framework.steps.iter() {
+            let samples_before = match framework_before.step(step) {
+                Some(samples_before) => samples_before,
+                None => continue,
+            };
+            let (summary_before, summary) = match (
+                Summary::from_samples(samples_before),
+                Summary::from_samples(samples),
+            ) {
+                (Some(summary_before), Some(summary)) => (summary_before, summary),
+                _ => continue,
+            };
+            // There's no ratio to a step that took no time
+            if summary_before.median <= 0.0 {
+                continue;
+            }
+            let test = stats::mann_whitney_u(samples_before, samples);
+            let significant = test.map(|test| test.is_significant()).unwrap_or(false);
+            let ratio = summary.median / summary_before.median;
+            let slower = ratio > 1.0 + threshold;
+            let conclusive = stats::can_be_significant(samples_before.len(), samples.len());
+            steps.push(StepDiff {
+                framework: framework.name.clone(),
+                step: step.clone(),
+                before_median: summary_before.median,
+                after_median: summary.median,
+                ratio,
+                p: test.map(|test| test.p),
+                significant,
+                regressed: significant && slower,
+                too_few_samples: slower && !conclusive,
+            });
+        }
+    }
+    steps
+}
+
+/// How each framework's steps in `after` changed from the same steps in
+/// `before`, flagging those significantly more than `threshold` slower, and
+/// those slower with too few samples to tell. Steps that took no time in
+/// `before` are left out.
+pub fn diff(before: &[Benchmark], after: &[Benchmark], threshold: f64) -> Vec<StepDiff> {
+    step_diffs(
+        &framework_samples(before),
+        &framework_samples(after),
+        threshold,
+    )
+}
+
+/// Format a ratio as a percent change.
+fn change(ratio: f64) -> String {
+    format!("{:+.1}%", (ratio - 1.0) * 100.0)
+}
+
+const CSV_HEADER: [&str; 18] = [
+    "run",
+    "framework",
+    "language",
+    "step",
+    "n",
+    "mean_ms",
+    "median_ms",
+    "stddev_ms",
+    "mad_ms",
+    "min_ms",
+    "max_ms",
+    "ci95_low_ms",
+    "ci95_high_ms",
+    "baseline_median_ms",
+    "ratio_to_baseline",
+    "p_to_baseline",
+    "regressed",
+    "too_few_samples",
+];
+
+impl Report {
+    /// Report on runs, each a label and the benchmarks in it, comparing each
+    /// run with the one before it.
+    pub fn new(runs: &[(String, Vec<Benchmark>)], threshold: f64) -> Self {
+        Report::build(runs, threshold, |i| i - 1)
+    }
+
+    /// Report on a baseline run followed by runs compared with it.
+    pub fn against_baseline(
+        baseline: (String, Vec<Benchmark>),
+        runs: &[(String, Vec<Benchmark>)],
+        threshold: f64,
+    ) -> Self {
+        let mut all = vec![baseline];
+        all.extend(runs.iter().cloned());
+        Report::build(&all, threshold, |_| 0)
+    }
+
+    /// Compare each run after the first with the run at `before(index)`.
+    fn build(
+        runs: &[(String, Vec<Benchmark>)],
+        threshold: f64,
+        before: impl Fn(usize) -> usize,
+    ) -> Self {
+        let samples = runs
+            .iter()
+            .map(|(run, benchmarks)| (run.as_str(), framework_samples(benchmarks)))
+            .collect::<Vec<_>>();
+        Report {
+            threshold,
+            runs: samples
+                .iter()
+                .map(|(run, frameworks)| run_stats(run, frameworks))
+                .collect(),
+            diffs: (1..samples.len())
+                .map(|i| {
+                    let (before_run, before_frameworks) = &samples[before(i)];
+                    let (after_run, after_frameworks) = &samples[i];
+                    RunDiff {
+                        before: before_run.to_string(),
+                        after: after_run.to_string(),
+                        steps: step_diffs(before_frameworks, after_frameworks, threshold),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+
+    /// A table of each run's framework and step statistics, with their
+    /// change from the run they're compared with.
+    pub fn to_csv(&self) -> String {
+        let mut lines = vec![CSV_HEADER.join(",")];
+        for (i, run) in self.runs.iter().enumerate() {
+            let diff = i.checked_sub(1).and_then(|i| self.diffs.get(i));
+            for framework in run.frameworks.iter() {
+                for step in framework.steps.iter() {
+                    let step_diff = diff.and_then(|diff| {
+                        diff.steps
+                            .iter()
+                            .find(|d| d.framework == framework.framework && d.step == step.step)
+                    });
+                    let summary = &step.summary;
+                    let line = vec![
+                        run.run.clone(),
+                        framework.framework.clone(),
+                        optional(framework.language.as_ref()),
+                        step.step.clone(),
+                        summary.n.to_string(),
+                        summary.mean.to_string(),
+                        summary.median.to_string(),
+                        summary.stddev.to_string(),
+                        summary.mad.to_string(),
+                        summary.min.to_string(),
+                        summary.max.to_string(),
+                        summary.ci95.0.to_string(),
+                        summary.ci95.1.to_string(),
+                        optional(step_diff.map(|d| d.before_median)),
+                        optional(step_diff.map(|d| d.ratio)),
+                        optional(step_diff.and_then(|d| d.p)),
+                        optional(step_diff.map(|d| d.regressed)),
+                        optional(step_diff.map(|d| d.too_few_samples)),
+                    ]
+                    .into_iter()
+                    .map(csv_field)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                    lines.push(line);
+                }
+            }
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// A section per run with a table per framework, then a section per
+    /// diff. Significant changes are in bold.
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        for run in self.runs.iter() {
+            md += &format!("## {}\n\n", run.run);
+            for framework in run.frameworks.iter() {
+                md += &format!("### {}", framework.framework);
+                if let Some(language) = framework.language.as_ref() {
+                    md += &format!(" ({})", language);
+                }
+                md += "\n\n";
+                if framework.failed > 0 || framework.excluded > 0 {
+                    md += &format!(
+                        "{} failed runs, {} excluded runs\n\n",
+                        framework.failed, framework.excluded
+                    );
+                }
+                if framework.steps.is_empty() {
+                    md += "No successful runs.\n\n";
+                    continue;
+                }
+                md += "| step | n | median | mean | 95% CI | sd | min | max |\n";
+                md += "|---|--:|--:|--:|--:|--:|--:|--:|\n";
+                for step in framework.steps.iter() {
+                    let s = &step.summary;
+                    md += &format!(
+                        "| {} | {} | {:.1}ms | {:.1}ms | {:.1} to {:.1} | {:.1} | {:.1} | {:.1} |\n",
+                        step.step, s.n, s.median, s.mean, s.ci95.0, s.ci95.1, s.stddev, s.min, s.max
+                    );
+                }
+                md += "\n";
+            }
+        }
+        for diff in self.diffs.iter() {
+            md += &format!("## {} to {}\n\n", diff.before, diff.after);
+            if diff.steps.is_empty() {
+                md += "No steps in common.\n\n";
+                continue;
+            }
+            md += &format!(
+                "{} regressions, steps significantly more than {:.0}% slower.\n\n",
+                diff.regressions(),
+                self.threshold * 100.0
+            );
+            if diff.too_few_samples() > 0 {
+                md += &format!(
+                    "{} steps are slower than that but have too few samples to tell whether \
+                     they regressed, compare runs of at least 4 iterations.\n\n",
+                    diff.too_few_samples()
+                );
+            }
+            md += "| framework | step | before | after | change | p | regressed |\n";
+            md += "|---|---|--:|--:|--:|--:|---|\n";
+            for step in diff.steps.iter() {
+                let change = if step.significant {
+                    format!("**{}**", change(step.ratio))
+                } else {
+                    change(step.ratio)
+                };
+                md += &format!(
+                    "| {} | {} | {:.1}ms | {:.1}ms | {} | {} | {} |\n",
+                    step.framework,
+                    step.step,
+                    step.before_median,
+                    step.after_median,
+                    change,
+                    step.p
+                        .map(|p| format!("{:.3}", p))
+                        .unwrap_or_else(|| "-".into()),
+                    if step.regressed {
+                        "yes"
+                    } else if step.too_few_samples {
+                        "too few samples"
+                    } else {
+                        ""
+                    }
+                );
+            }
+            md += "\n";
+        }
+        md
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn benchmark(name: &str, create: f64, delete: f64) -> Benchmark {
+        Benchmark::sequential(name, &[("create todos", create), ("delete todos", delete)])
+    }
+
+    fn runs() -> Vec<(String, Vec<Benchmark>)> {
+        let before = (1..=5)
+            .map(|i| benchmark("a", 10.0 + i as f64, 5.0))
+            .collect();
+        let mut after = (1..=5)
+            .map(|i| benchmark("a", 20.0 + i as f64, 5.0))
+            .collect::<Vec<_>>();
+        after[0].failed_message = Some("oops".into());
+        after.push(benchmark("b", 1.0, 1.0));
+        vec![("before.json".into(), before), ("after.json".into(), after)]
+    }
+
+    #[test]
+    fn summarizes_each_step_and_the_total() {
+        let report = Report::new(&runs(), DEFAULT_THRESHOLD);
+        let a = &report.runs[1].frameworks[0];
+        assert_eq!(a.failed, 1);
+        let steps = a.steps.iter().map(|s| s.step.as_str()).collect::<Vec<_>>();
+        assert_eq!(steps, vec!["create todos", "delete todos", "total"]);
+        assert_eq!(a.steps[0].summary.n, 4);
+        assert_eq!(a.steps[0].summary.median, 23.5);
+        assert_eq!(a.steps[2].summary.median, 28.5);
+    }
+
+    #[test]
+    fn diffs_steps_in_both_runs() {
+        let report = Report::new(&runs(), DEFAULT_THRESHOLD);
+        assert_eq!(report.diffs.len(), 1);
+        let diff = &report.diffs[0];
+        assert_eq!(diff.steps.len(), 3);
+        let create = &diff.steps[0];
+        assert_eq!((create.before_median, create.after_median), (13.0, 23.5));
+        assert!(create.significant);
+        assert!(create.regressed);
+        let delete = &diff.steps[1];
+        assert_eq!(delete.ratio, 1.0);
+        assert!(!delete.significant);
+        assert!(!delete.regressed);
+    }
+
+    #[test]
+    fn flags_regressions_past_the_threshold() {
+        let runs = runs();
+        let create = |diffs: Vec<StepDiff>| diffs[0].regressed;
+        assert!(create(diff(&runs[0].1, &runs[1].1, 0.5)));
+        assert!(!create(diff(&runs[0].1, &runs[1].1, 1.0)));
+        // Getting faster is never a regression
+        assert!(!create(diff(&runs[1].1, &runs[0].1, 0.0)));
+    }
+
+    #[test]
+    fn noise_is_not_a_regression() {
+        let run = |creates: &[f64]| {
+            creates
+                .iter()
+                .map(|create| benchmark("a", *create, 5.0))
+                .collect::<Vec<_>>()
+        };
+        let before = run(&[10.0, 30.0, 12.0, 28.0, 11.0]);
+        let after = run(&[12.0, 31.0, 14.0, 29.0, 13.0]);
+        let create = &diff(&before, &after, DEFAULT_THRESHOLD)[0];
+        assert!(create.ratio > 1.0 + DEFAULT_THRESHOLD);
+        assert!(!create.significant);
+        assert!(!create.regressed);
+        assert!(!create.too_few_samples);
+
+        // Nor is a single sample, however much slower, but that's told apart
+        // from not having slowed down
+        let create = &diff(&run(&[10.0]), &run(&[100.0]), DEFAULT_THRESHOLD)[0];
+        assert!(!create.regressed);
+        assert!(create.too_few_samples);
+        let create = &diff(&run(&[10.0]), &run(&[10.5]), DEFAULT_THRESHOLD)[0];
+        assert!(!create.too_few_samples);
+    }
+
+    #[test]
+    fn leaves_out_steps_that_took_no_time_before() {
+        let before = vec![benchmark("a", 0.0, 5.0), benchmark("a", 0.0, 5.0)];
+        let after = vec![benchmark("a", 3.0, 5.0), benchmark("a", 4.0, 5.0)];
+        let steps = diff(&before, &after, DEFAULT_THRESHOLD)
+            .into_iter()
+            .map(|step| step.step)
+            .collect::<Vec<_>>();
+        assert_eq!(steps, vec!["delete todos", "total"]);
+    }
+
+    #[test]
+    fn compares_every_run_with_the_baseline() {
+        let mut runs = runs();
+        let baseline = runs.remove(0);
+        runs.push(("again.json".into(), runs[0].1.clone()));
+        let report = Report::against_baseline(baseline, &runs, DEFAULT_THRESHOLD);
+        assert_eq!(report.runs.len(), 3);
+        let befores = report
+            .diffs
+            .iter()
+            .map(|d| d.before.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(befores, vec!["before.json", "before.json"]);
+        assert!(report.diffs[1].steps[0].regressed);
+    }
+
+    #[test]
+    fn formats_every_step() {
+        let report = Report::new(&runs(), DEFAULT_THRESHOLD);
+        let csv = report.to_csv();
+        let lines = csv.lines().collect::<Vec<_>>();
+        // header, 3 steps of "a" in each run, 3 of "b"
+        assert_eq!(lines.len(), 10);
+        assert!(lines[4].starts_with("after.json,a,,create todos,4,"));
+        assert!(lines[4].contains(",13,"));
+
+        let md = report.to_markdown();
+        assert!(md.contains("## before.json to after.json"));
+        assert!(md.contains("2 regressions, steps significantly more than 10% slower."));
+        assert!(md.contains("| a | create todos | 13.0ms | 23.5ms | **+80.8%** | 0.016 | yes |"));
+        assert!(report.to_json().unwrap().contains("\"ratio\""));
+    }
+
+    #[test]
+    fn formats_steps_with_too_few_samples() {
+        let runs = vec![
+            ("before.json".into(), vec![benchmark("a", 10.0, 5.0)]),
+            ("after.json".into(), vec![benchmark("a", 100.0, 5.0)]),
+        ];
+        let report = Report::new(&runs, DEFAULT_THRESHOLD);
+        assert_eq!(report.diffs[0].regressions(), 0);
+        assert_eq!(report.diffs[0].too_few_samples(), 2);
+
+        let md = report.to_markdown();
+        assert!(md.contains("2 steps are slower than that but have too few samples"));
+        assert!(md.contains(
+            "| a | create todos | 10.0ms | 100.0ms | +900.0% | 1.000 | too few samples |"
+        ));
+        assert!(report
+            .to_csv()
+            .lines()
+            .nth(4)
+            .unwrap()
+            .ends_with(",false,true"));
+    }
+}
@@ -0,0 +1,152 @@
+//! Versions of the stored benchmark format, and migrations between them.
+//!
+//! Benchmarks are stored in an envelope holding the version they were written
+//! with. Older data is migrated one version at a time as a `serde_json::Value`
+//! before it is deserialized:
+//!
+//! * 0 - unversioned, no envelope. `BenchmarkStep::cycles` was the number of
+//!   operations a step looped over.
+//! * 1 - `BenchmarkStep::cycles` holds the milliseconds each operation took.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::benchmark::Benchmark;
+
+/// The version of data written now.
+pub const VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// `MIGRATIONS[n]` migrates data from version `n` to `n + 1`.
+const MIGRATIONS: [fn(Value) -> Result<Value, String>; 1] = [cycle_counts_to_samples];
+
+#[derive(Serialize, Deserialize)]
+pub struct Envelope<T> {
+    pub version: u32,
+    pub data: T,
+}
+
+/// Wrap benchmarks in an envelope of the current version.
+pub fn envelope(benchmarks: &Vec<Benchmark>) -> Envelope<&Vec<Benchmark>> {
+    Envelope {
+        version: VERSION,
+        data: benchmarks,
+    }
+}
+
+/// Steps of every benchmark in version 0 or 1 data.
+fn steps_mut(data: &mut Value) -> impl Iterator<Item = &mut Value> {
+    data.as_array_mut()
+        .into_iter()
+        .flatten()
+        .filter_map(|benchmark| benchmark.get_mut("steps")?.as_array_mut())
+        .flatten()
+}
+
+/// 0 to 1. The old operation counts can't become per operation times, so
+/// they're dropped. Data written unversioned after the change already has
+/// times, which are kept.
+fn cycle_counts_to_samples(mut data: Value) -> Result<Value, String> {
+    if !data.is_array() {
+        return Err("expected a list of benchmarks".into());
+    }
+    for step in steps_mut(&mut data) {
+        if let Some(cycles) = step.get_mut("cycles") {
+            if cycles.is_number() {
+                *cycles = Value::Null;
+            }
+        }
+    }
+    Ok(data)
+}
+
+/// Parse benchmarks written with any version, migrating them to the current
+/// one.
+pub fn parse(json: &str) -> Result<Vec<Benchmark>, String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| format!("not json: {}", e))?;
+    let Envelope { version, mut data } = if value.is_object() {
+        serde_json::from_value(value).map_err(|e| format!("not an envelope: {}", e))?
+    } else {
+        Envelope {
+            version: 0,
+            data: value,
+        }
+    };
+    if version > VERSION {
+        return Err(format!(
+            "written by a newer version of todo-mvc-bench (version {}, this is {})",
+            version, VERSION
+        ));
+    }
+    for (from, migrate) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        data =
+            migrate(data).map_err(|e| format!("could not migrate from version {}: {}", from, e))?;
+    }
+    serde_json::from_value(data)
+        .map_err(|e| format!("could not read version {} benchmarks: {}", version, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_cycle_counts_to_samples() {
+        let data = serde_json::json!([{
+            "name": "a",
+            "steps": [
+                {"name": "load", "start": 0.0, "end": 1.0, "cycles": null},
+                {"name": "create todos", "start": 1.0, "end": 2.0, "cycles": 100},
+                {"name": "edit todos", "start": 2.0, "end": 3.0, "cycles": [0.5, 0.5]}
+            ],
+            "failed_message": null,
+            "language": "rust"
+        }]);
+        let migrated = cycle_counts_to_samples(data).unwrap();
+        assert_eq!(migrated[0]["steps"][0]["cycles"], Value::Null);
+        assert_eq!(migrated[0]["steps"][1]["cycles"], Value::Null);
+        assert_eq!(
+            migrated[0]["steps"][2]["cycles"],
+            serde_json::json!([0.5, 0.5])
+        );
+        assert!(cycle_counts_to_samples(serde_json::json!("a")).is_err());
+    }
+
+    #[test]
+    fn reads_unversioned_data() {
+        let benchmarks = parse(
+            r#"[{
+                "name": "a",
+                "steps": [{"name": "create todos", "start": 1.0, "end": 2.0, "cycles": 100}],
+                "failed_message": null,
+                "language": "rust"
+            }]"#,
+        )
+        .unwrap();
+        assert_eq!(benchmarks[0].steps[0].cycles, None);
+    }
+
+    #[test]
+    fn reads_what_it_writes() {
+        let mut benchmark = Benchmark::new();
+        benchmark.name = "a".into();
+        let json = serde_json::to_string(&envelope(&vec![benchmark])).unwrap();
+        assert_eq!(parse(&json).unwrap()[0].name, "a");
+    }
+
+    #[test]
+    fn reports_data_it_cannot_read() {
+        assert!(parse("{\"benchmarks\": ")
+            .unwrap_err()
+            .starts_with("not json"));
+        assert!(parse("{\"benchmarks\": []}")
+            .unwrap_err()
+            .starts_with("not an envelope"));
+        assert!(parse("{\"version\": 1000, \"data\": []}")
+            .unwrap_err()
+            .contains("newer version"));
+        assert!(parse("\"a\"")
+            .unwrap_err()
+            .starts_with("could not migrate from version 0"));
+        let err = parse(&format!("{{\"version\": {}, \"data\": [{{}}]}}", VERSION)).unwrap_err();
+        assert!(err.starts_with("could not read version"), "{}", err);
+    }
+}
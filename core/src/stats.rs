@@ -0,0 +1,523 @@
+//! Descriptive statistics over benchmark samples.
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, fmt};
+
+/// Return a sorted copy of the samples.
+pub fn sorted(samples: &[f64]) -> Vec<f64> {
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    sorted
+}
+
+/// The `p`th percentile (0 to 100) of already sorted samples, interpolating
+/// linearly between the closest ranks.
+pub fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p / 100.0).clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64))
+}
+
+/// Latency percentiles of per operation samples.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Percentiles {
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+impl Percentiles {
+    pub fn from_samples(samples: &[f64]) -> Option<Self> {
+        let sorted = sorted(samples);
+        Some(Percentiles {
+            p50: percentile(&sorted, 50.0)?,
+            p95: percentile(&sorted, 95.0)?,
+            p99: percentile(&sorted, 99.0)?,
+        })
+    }
+}
+
+impl fmt::Display for Percentiles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "p50 {:.2}ms, p95 {:.2}ms, p99 {:.2}ms",
+            self.p50, self.p95, self.p99
+        )
+    }
+}
+
+/// The quartiles of samples, with Tukey's whiskers reaching the furthest
+/// samples within 1.5 IQR of the box and the samples past them as outliers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoxPlot {
+    pub low: f64,
+    pub q1: f64,
+    pub median: f64,
+    pub q3: f64,
+    pub high: f64,
+    pub outliers: Vec<f64>,
+}
+
+impl BoxPlot {
+    pub fn from_samples(samples: &[f64]) -> Option<Self> {
+        let sorted = sorted(samples);
+        let q1 = percentile(&sorted, 25.0)?;
+        let median = percentile(&sorted, 50.0)?;
+        let q3 = percentile(&sorted, 75.0)?;
+        let iqr = q3 - q1;
+        let (fence_low, fence_high) = (q1 - 1.5 * iqr, q3 + 1.5 * iqr);
+        let (inside, outliers): (Vec<f64>, Vec<f64>) = sorted
+            .into_iter()
+            .partition(|sample| (fence_low..=fence_high).contains(sample));
+        Some(BoxPlot {
+            low: inside.first().cloned().unwrap_or(q1),
+            q1,
+            median,
+            q3,
+            high: inside.last().cloned().unwrap_or(q3),
+            outliers,
+        })
+    }
+}
+
+impl fmt::Display for BoxPlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "median {:.1}ms, quartiles {:.1} to {:.1}ms, whiskers {:.1} to {:.1}ms, {} outliers",
+            self.median,
+            self.q1,
+            self.q3,
+            self.low,
+            self.high,
+            self.outliers.len()
+        )
+    }
+}
+
+/// Two-sided 95% critical values of Student's t distribution for 1 to 30
+/// degrees of freedom.
+const T_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+
+/// The two-sided 95% critical value of Student's t distribution, using the
+/// normal distribution's past 30 degrees of freedom.
+fn t_95(degrees_of_freedom: usize) -> f64 {
+    match degrees_of_freedom {
+        0 => f64::NAN,
+        df if df <= T_95.len() => T_95[df - 1],
+        _ => 1.96,
+    }
+}
+
+/// A summary of repeated samples of one measurement.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub n: usize,
+    pub mean: f64,
+    pub median: f64,
+    /// Sample standard deviation, zero for a single sample.
+    pub stddev: f64,
+    /// Median absolute deviation from the median.
+    pub mad: f64,
+    pub min: f64,
+    pub max: f64,
+    /// 95% confidence interval of the mean. This is just the mean for a single
+    /// sample.
+    pub ci95: (f64, f64),
+}
+
+impl Summary {
+    pub fn from_samples(samples: &[f64]) -> Option<Self> {
+        let ordered = sorted(samples);
+        let n = ordered.len();
+        let median = percentile(&ordered, 50.0)?;
+        let mean = ordered.iter().sum::<f64>() / n as f64;
+        let stddev = if n > 1 {
+            let squares = ordered.iter().map(|x| (x - mean).powi(2)).sum::<f64>();
+            (squares / (n - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+        let deviations = ordered
+            .iter()
+            .map(|x| (x - median).abs())
+            .collect::<Vec<_>>();
+        let mad = percentile(&sorted(&deviations), 50.0)?;
+        let half_width = if n > 1 {
+            t_95(n - 1) * stddev / (n as f64).sqrt()
+        } else {
+            0.0
+        };
+        Some(Summary {
+            n,
+            mean,
+            median,
+            stddev,
+            mad,
+            min: ordered[0],
+            max: ordered[n - 1],
+            ci95: (mean - half_width, mean + half_width),
+        })
+    }
+
+    /// Half the width of the 95% confidence interval.
+    pub fn ci95_half_width(&self) -> f64 {
+        (self.ci95.1 - self.ci95.0) / 2.0
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "median {:.1}ms, mean {:.1}ms (95% CI {:.1} to {:.1}), sd {:.1}, MAD {:.1}, \
+             range {:.1} to {:.1}, n={}",
+            self.median,
+            self.mean,
+            self.ci95.0,
+            self.ci95.1,
+            self.stddev,
+            self.mad,
+            self.min,
+            self.max,
+            self.n
+        )
+    }
+}
+
+/// Which samples count as outliers, by how far outside the interquartile
+/// range `Q1..Q3` they fall.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum OutlierFences {
+    /// Keep every sample.
+    Off,
+    /// Tukey's inner fences, `Q1 - 1.5 IQR` to `Q3 + 1.5 IQR`.
+    Inner,
+    /// Tukey's outer fences, `Q1 - 3 IQR` to `Q3 + 3 IQR`. Only "far out"
+    /// samples are rejected.
+    Outer,
+}
+
+impl OutlierFences {
+    /// The range of samples that are not outliers. `None` when every sample
+    /// is kept, or there are too few samples for quartiles to mean anything.
+    pub fn bounds(&self, samples: &[f64]) -> Option<(f64, f64)> {
+        let k = match self {
+            OutlierFences::Off => return None,
+            OutlierFences::Inner => 1.5,
+            OutlierFences::Outer => 3.0,
+        };
+        if samples.len() < 4 {
+            return None;
+        }
+        let sorted = sorted(samples);
+        let q1 = percentile(&sorted, 25.0)?;
+        let q3 = percentile(&sorted, 75.0)?;
+        let iqr = q3 - q1;
+        Some((q1 - k * iqr, q3 + k * iqr))
+    }
+}
+
+/// Samples are significantly different below this p-value.
+pub const SIGNIFICANCE: f64 = 0.05;
+
+/// The complementary error function, with a fractional error below 1.2e-7
+/// (Numerical Recipes' `erfcc`).
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807
+                            + t * (-1.13520398
+                                + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))));
+    let ans = t * poly.exp();
+    if x >= 0.0 {
+        ans
+    } else {
+        2.0 - ans
+    }
+}
+
+/// The exact probability that U is at most `u` for samples of sizes `n1` and
+/// `n2` without ties, by counting the orderings that produce each U.
+fn exact_u_cdf(n1: usize, n2: usize, u: usize) -> f64 {
+    // counts[i][j][u] is the number of orderings of i and j samples with U = u
+    let mut counts: Vec<Vec<Vec<f64>>> = vec![vec![vec![]; n2 + 1]; n1 + 1];
+    for i in 0..=n1 {
+        for j in 0..=n2 {
+            counts[i][j] = if i == 0 || j == 0 {
+                vec![1.0]
+            } else {
+                (0..=i * j)
+                    .map(|u| {
+                        let with_larger = if u >= j {
+                            counts[i - 1][j].get(u - j).cloned().unwrap_or(0.0)
+                        } else {
+                            0.0
+                        };
+                        with_larger + counts[i][j - 1].get(u).cloned().unwrap_or(0.0)
+                    })
+                    .collect()
+            };
+        }
+    }
+    let all = &counts[n1][n2];
+    all[..=u.min(all.len() - 1)].iter().sum::<f64>() / all.iter().sum::<f64>()
+}
+
+/// The result of a two-sided Mann-Whitney U test.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MannWhitney {
+    /// The smaller of the two U statistics.
+    pub u: f64,
+    pub p: f64,
+    /// The rank-biserial correlation, from -1 when every sample of `a` is
+    /// smaller than every sample of `b` to 1 when every one is larger.
+    pub effect_size: f64,
+}
+
+impl MannWhitney {
+    pub fn is_significant(&self) -> bool {
+        self.p < SIGNIFICANCE
+    }
+}
+
+/// Test whether samples `a` and `b` come from the same distribution, without
+/// assuming that distribution is normal. Small samples without ties get an
+/// exact p-value, others use the normal approximation with a tie correction.
+pub fn mann_whitney_u(a: &[f64], b: &[f64]) -> Option<MannWhitney> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    let (n1, n2) = (a.len(), b.len());
+    let mut pooled = a
+        .iter()
+        .map(|x| (*x, true))
+        .chain(b.iter().map(|x| (*x, false)))
+        .collect::<Vec<_>>();
+    pooled.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap_or(Ordering::Equal));
+
+    // Rank the pooled samples, giving tied samples their average rank
+    let mut rank_sum_a = 0.0;
+    let mut tie_term = 0.0;
+    let mut i = 0;
+    while i < pooled.len() {
+        let mut j = i;
+        while j + 1 < pooled.len() && pooled[j + 1].0 == pooled[i].0 {
+            j += 1;
+        }
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        let tied = (j - i + 1) as f64;
+        tie_term += tied.powi(3) - tied;
+        rank_sum_a += rank * pooled[i..=j].iter().filter(|(_, is_a)| *is_a).count() as f64;
+        i = j + 1;
+    }
+
+    let pairs = (n1 * n2) as f64;
+    let u_a = rank_sum_a - (n1 * (n1 + 1)) as f64 / 2.0;
+    let u_b = pairs - u_a;
+    let u = u_a.min(u_b);
+    let effect_size = (u_a - u_b) / pairs;
+
+    let p = if tie_term == 0.0 && n1 <= 20 && n2 <= 20 {
+        2.0 * exact_u_cdf(n1, n2, u as usize)
+    } else {
+        let n = (n1 + n2) as f64;
+        let variance = pairs / 12.0 * ((n + 1.0) - tie_term / (n * (n - 1.0)));
+        if variance <= 0.0 {
+            1.0
+        } else {
+            let z = ((pairs / 2.0 - u).abs() - 0.5).max(0.0) / variance.sqrt();
+            erfc(z / std::f64::consts::SQRT_2)
+        }
+    };
+
+    Some(MannWhitney {
+        u,
+        p: p.min(1.0),
+        effect_size,
+    })
+}
+
+/// Whether samples of sizes `n1` and `n2` can differ significantly at all,
+/// which takes about four of each, or more of one than the other.
+pub fn can_be_significant(n1: usize, n2: usize) -> bool {
+    // The most extreme difference there is, with every sample of one
+    // outranking every sample of the other
+    let a = (0..n1).map(|i| i as f64).collect::<Vec<_>>();
+    let b = (n1..n1 + n2).map(|i| i as f64).collect::<Vec<_>>();
+    mann_whitney_u(&a, &b)
+        .map(|test| test.is_significant())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn summarizes_samples() {
+        let summary = Summary::from_samples(&[9.0, 2.0, 4.0, 4.0, 5.0, 4.0, 7.0, 5.0]).unwrap();
+        assert_eq!(summary.n, 8);
+        assert_close(summary.mean, 5.0);
+        assert_close(summary.median, 4.5);
+        // Sample (n - 1) standard deviation, sqrt(32 / 7)
+        assert_close(summary.stddev, 2.13809);
+        assert_close(summary.mad, 0.5);
+        assert_eq!((summary.min, summary.max), (2.0, 9.0));
+        // t for 7 degrees of freedom is 2.365
+        assert_close(summary.ci95_half_width(), 2.365 * 2.13809 / 8f64.sqrt());
+        assert_close(summary.ci95.0, 5.0 - summary.ci95_half_width());
+    }
+
+    #[test]
+    fn summary_ci_uses_the_normal_past_the_t_table() {
+        let samples = (0..40).map(|i| (i % 2) as f64 * 2.0).collect::<Vec<_>>();
+        let summary = Summary::from_samples(&samples).unwrap();
+        assert_close(summary.mean, 1.0);
+        assert_close(
+            summary.ci95_half_width(),
+            1.96 * (40.0f64 / 39.0).sqrt() / 40f64.sqrt(),
+        );
+    }
+
+    #[test]
+    fn summary_of_one_or_no_samples() {
+        let one = Summary::from_samples(&[3.0]).unwrap();
+        assert_eq!((one.n, one.mean, one.median), (1, 3.0, 3.0));
+        assert_eq!((one.stddev, one.mad), (0.0, 0.0));
+        assert_eq!(one.ci95, (3.0, 3.0));
+        assert_eq!(one.ci95_half_width(), 0.0);
+        assert!(Summary::from_samples(&[]).is_none());
+    }
+
+    #[test]
+    fn outlier_fences_bound_the_interquartile_range() {
+        let samples = [1.0, 2.0, 3.0, 4.0, 100.0];
+        assert_eq!(OutlierFences::Off.bounds(&samples), None);
+        // Q1 is 2 and Q3 is 4
+        assert_eq!(OutlierFences::Inner.bounds(&samples), Some((-1.0, 7.0)));
+        assert_eq!(OutlierFences::Outer.bounds(&samples), Some((-4.0, 10.0)));
+    }
+
+    #[test]
+    fn outlier_fences_need_four_samples() {
+        assert_eq!(OutlierFences::Inner.bounds(&[1.0, 2.0, 100.0]), None);
+        assert_eq!(OutlierFences::Outer.bounds(&[]), None);
+        assert!(OutlierFences::Inner
+            .bounds(&[1.0, 2.0, 3.0, 100.0])
+            .is_some());
+    }
+
+    #[test]
+    fn percentiles_interpolate_between_ranks() {
+        let sorted = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_close(percentile(&sorted, 50.0).unwrap(), 3.0);
+        assert_close(percentile(&sorted, 10.0).unwrap(), 1.4);
+        assert_close(percentile(&sorted, 95.0).unwrap(), 4.8);
+        assert_close(percentile(&[2.0, 4.0], 25.0).unwrap(), 2.5);
+    }
+
+    #[test]
+    fn percentiles_clamp_p() {
+        let sorted = [1.0, 2.0, 3.0];
+        assert_close(percentile(&sorted, -10.0).unwrap(), 1.0);
+        assert_close(percentile(&sorted, 150.0).unwrap(), 3.0);
+    }
+
+    #[test]
+    fn percentiles_of_one_or_no_samples() {
+        assert_eq!(
+            Percentiles::from_samples(&[7.0]),
+            Some(Percentiles {
+                p50: 7.0,
+                p95: 7.0,
+                p99: 7.0
+            })
+        );
+        assert!(percentile(&[], 50.0).is_none());
+        assert!(Percentiles::from_samples(&[]).is_none());
+    }
+
+    #[test]
+    fn percentiles_sort_the_samples() {
+        let samples = (1..=100).rev().map(f64::from).collect::<Vec<_>>();
+        let p = Percentiles::from_samples(&samples).unwrap();
+        assert_close(p.p50, 50.5);
+        assert_close(p.p95, 95.05);
+        assert_close(p.p99, 99.01);
+    }
+
+    #[test]
+    fn separated_samples_differ() {
+        let test = mann_whitney_u(&[1.0, 2.0, 3.0, 4.0, 5.0], &[6.0, 7.0, 8.0, 9.0, 10.0]).unwrap();
+        assert_close(test.u, 0.0);
+        // 2 of the 252 orderings are this extreme
+        assert_close(test.p, 2.0 / 252.0);
+        assert_close(test.effect_size, -1.0);
+        assert!(test.is_significant());
+    }
+
+    #[test]
+    fn interleaved_samples_do_not_differ() {
+        let test = mann_whitney_u(&[1.0, 3.0, 5.0, 7.0], &[2.0, 4.0, 6.0, 8.0]).unwrap();
+        assert_close(test.u, 6.0);
+        assert!(test.p > 0.5, "{}", test.p);
+        assert!(!test.is_significant());
+    }
+
+    #[test]
+    fn ties_use_the_normal_approximation() {
+        let same = mann_whitney_u(&[1.0, 1.0, 1.0], &[1.0, 1.0, 1.0]).unwrap();
+        assert_close(same.p, 1.0);
+        assert_close(same.effect_size, 0.0);
+
+        let a = (0..30).map(|i| (i % 10) as f64).collect::<Vec<_>>();
+        let b = (0..30).map(|i| (i % 10) as f64 + 5.0).collect::<Vec<_>>();
+        let test = mann_whitney_u(&b, &a).unwrap();
+        assert!(test.is_significant(), "{}", test.p);
+        assert!(test.effect_size > 0.0);
+    }
+
+    #[test]
+    fn few_samples_can_not_differ_significantly() {
+        assert!(!can_be_significant(1, 1));
+        assert!(!can_be_significant(3, 3));
+        // 2 of the 35 orderings are as extreme as can be
+        assert!(!can_be_significant(3, 4));
+        assert!(can_be_significant(4, 4));
+        assert!(can_be_significant(3, 5));
+        assert!(!can_be_significant(0, 10));
+        assert!(can_be_significant(2, 20));
+    }
+
+    #[test]
+    fn box_plot_whiskers_stop_at_the_fences() {
+        let plot = BoxPlot::from_samples(&[5.0, 1.0, 2.0, 3.0, 4.0, 40.0]).unwrap();
+        assert_close(plot.q1, 2.25);
+        assert_close(plot.median, 3.5);
+        assert_close(plot.q3, 4.75);
+        assert_eq!((plot.low, plot.high), (1.0, 5.0));
+        assert_eq!(plot.outliers, vec![40.0]);
+
+        let single = BoxPlot::from_samples(&[7.0]).unwrap();
+        assert_eq!((single.low, single.median, single.high), (7.0, 7.0, 7.0));
+        assert!(BoxPlot::from_samples(&[]).is_none());
+    }
+}
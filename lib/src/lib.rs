@@ -7,6 +7,8 @@ use mogwai::{
     futures::stream::{self, Stream, StreamExt},
     prelude::broadcast,
 };
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{MutationObserver, MutationObserverInit, Node, Window};
 
 #[derive(Clone)]
 pub struct Found<T> {
@@ -116,3 +118,100 @@ pub async fn wait_until_next_for<T>(
         }
     }
 }
+
+/// Wait until the given function returns `Some`, checking it once up front and
+/// then again each time a `MutationObserver` on `target` fires.
+///
+/// Unlike [`wait_for`] this doesn't poll, so the main thread is left to the
+/// page being observed.
+pub async fn wait_for_mutation<T>(
+    target: &Node,
+    options: &MutationObserverInit,
+    mut f: impl FnMut() -> Option<T>,
+    timeout_seconds: f64,
+) -> Result<Found<T>, f64> {
+    let performance = mogwai::utils::window()
+        .performance()
+        .expect("no performance object");
+    let start = performance.now();
+    let elapsed = || (performance.now() - start) / 1000.0;
+
+    if let Some(t) = f() {
+        return Ok(Found {
+            found: t,
+            elapsed_seconds: elapsed(),
+        });
+    }
+
+    let (tx_mutation, mut rx_mutation) = futures::channel::mpsc::unbounded::<()>();
+    let callback = Closure::wrap(Box::new(move |_records: JsValue, _observer: JsValue| {
+        let _ = tx_mutation.unbounded_send(());
+    }) as Box<dyn FnMut(JsValue, JsValue)>);
+    let observer = MutationObserver::new(callback.as_ref().unchecked_ref()).map_err(|e| {
+        log::error!("could not create mutation observer: {:?}", e);
+        elapsed()
+    })?;
+    observer
+        .observe_with_options(target, options)
+        .map_err(|e| {
+            log::error!("could not observe mutations: {:?}", e);
+            elapsed()
+        })?;
+
+    let mut timeout = mogwai::time::wait_approx(timeout_seconds * 1000.0).fuse();
+    let result = loop {
+        mogwai::futures::select! {
+            _ = rx_mutation.next() => {
+                if let Some(t) = f() {
+                    break Ok(Found {
+                        found: t,
+                        elapsed_seconds: elapsed(),
+                    });
+                }
+            }
+            _ = timeout => {
+                break Err(elapsed());
+            }
+        }
+    };
+    observer.disconnect();
+    result
+}
+
+/// Wait while the given function returns true, checking it each time a
+/// `MutationObserver` on `target` fires.
+pub async fn wait_while_mutation(
+    target: &Node,
+    options: &MutationObserverInit,
+    mut f: impl FnMut() -> bool,
+    timeout_seconds: f64,
+) -> Result<Found<()>, f64> {
+    wait_for_mutation(
+        target,
+        options,
+        move || if f() { None } else { Some(()) },
+        timeout_seconds,
+    )
+    .await
+}
+
+/// Resolve with the timestamp of the given window's next animation frame.
+async fn next_animation_frame(window: &Window) -> Result<f64, JsValue> {
+    let (tx, rx) = futures::channel::oneshot::channel();
+    let callback = Closure::once(move |timestamp: f64| {
+        let _ = tx.send(timestamp);
+    });
+    window.request_animation_frame(callback.as_ref().unchecked_ref())?;
+    rx.await
+        .map_err(|_| JsValue::from_str("animation frame was canceled"))
+}
+
+/// Wait until the given window has painted the changes made so far.
+///
+/// A `requestAnimationFrame` callback runs before the style, layout and paint
+/// of its frame, so the first point after those is the next frame's callback.
+pub async fn wait_for_paint(window: &Window) -> Result<(), JsValue> {
+    next_animation_frame(window).await?;
+    next_animation_frame(window).await?;
+    Ok(())
+}
@@ -46,7 +46,7 @@ build() {
 build_release() {
     wasm-pack build --release --target web || exit 1
     mkdir -p release
-    cp -R pkg index.html style.css frameworks release/
+    cp -R pkg index.html style.css frameworks frameworks.json frameworks.schema.json release/
     sleep 1
     tar czvf release.tar.gz release || exit 1
     sleep 1